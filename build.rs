@@ -0,0 +1,21 @@
+//! Compiles the C++ side of the libzim bindings declared in `src/binding.rs`.
+
+fn main() {
+    #[cfg(all(feature = "libzim", not(feature = "pure-rust")))]
+    {
+        // 9.3 added `OpenConfig::preloadDirentRanges` and the global, byte-sized
+        // cluster cache the bindings rely on.
+        let libzim = pkg_config::Config::new()
+            .range_version("9.3.0".."10.0.0")
+            .probe("libzim")
+            .unwrap_or_else(|err| panic!("libzim >= 9.3, < 10 not found: {err}"));
+        cxx_build::bridge("src/binding.rs")
+            .file("src/binding.cc")
+            .includes(&libzim.include_paths)
+            .flag_if_supported("-std=c++17")
+            .compile("zim-rs");
+        println!("cargo:rerun-if-changed=src/binding.rs");
+        println!("cargo:rerun-if-changed=src/binding.h");
+        println!("cargo:rerun-if-changed=src/binding.cc");
+    }
+}
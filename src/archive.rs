@@ -1,32 +1,217 @@
+#[cfg(not(feature = "pure-rust"))]
+use std::borrow::Cow;
+use std::{
+    io,
+    ops::{Bound, Range, RangeBounds},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(all(unix, not(feature = "pure-rust")))]
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+#[cfg(not(feature = "pure-rust"))]
 use cxx::UniquePtr;
-use zim_sys::binding::ffi;
 
+#[cfg(not(feature = "pure-rust"))]
+use crate::binding::ffi;
+
+#[cfg(not(feature = "pure-rust"))]
 use crate::{
     cxx::{string_from_ptr, vec_string_from_ptr},
     entry::Entry,
     item::Item,
     uuid::Uuid,
 };
+use crate::{
+    error::Error,
+    metadata::{ArchiveMetadata, Counter, Tags},
+};
 
+#[cfg(feature = "pure-rust")]
+pub use crate::native::archive::{
+    EntryRangeEfficient, EntryRangePath, EntryRangeTitle, IterEfficient, IterPath, IterTitle,
+};
+
+/// Handle to an opened ZIM archive.
+///
+/// Cloning is cheap: all clones share the same underlying archive, which is
+/// closed once the last clone and every `Entry`/`Item` read from it are dropped.
+#[derive(Clone)]
 pub struct Archive {
+    inner: Arc<ArchiveInner>,
+}
+
+#[cfg(not(feature = "pure-rust"))]
+struct ArchiveInner {
     ptr: UniquePtr<ffi::Archive>,
+    parts: Vec<ArchivePart>,
+}
+
+#[cfg(feature = "pure-rust")]
+struct ArchiveInner {
+    reader: crate::native::format::Reader,
+    parts: Vec<ArchivePart>,
 }
 
+// [SAFETY]
+// The archive is only accessed through `&ffi::Archive` once opened, and libzim's reader
+// is threadsafe, so it is valid to call libzim methods on it from different threads.
+// The pointer itself is owned by the `Arc` and freed exactly once.
+#[cfg(not(feature = "pure-rust"))]
+unsafe impl Sync for ArchiveInner {}
+#[cfg(not(feature = "pure-rust"))]
+unsafe impl Send for ArchiveInner {}
+
+#[cfg(feature = "pure-rust")]
+impl Archive {
+    pub(crate) fn from_reader(
+        reader: crate::native::format::Reader,
+        parts: Vec<ArchivePart>,
+    ) -> Archive {
+        Archive {
+            inner: Arc::new(ArchiveInner { reader, parts }),
+        }
+    }
+
+    pub(crate) fn reader(&self) -> &crate::native::format::Reader {
+        &self.inner.reader
+    }
+}
+
+#[cfg(not(feature = "pure-rust"))]
 impl Archive {
     pub(crate) fn inner_ref(&self) -> &ffi::Archive {
-        self.ptr.as_ref().unwrap()
+        self.inner.ptr.as_ref().unwrap()
+    }
+
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Archive>, parts: Vec<ArchivePart>) -> Archive {
+        Archive {
+            inner: Arc::new(ArchiveInner { ptr, parts }),
+        }
     }
 
-    pub fn new(path: &str) -> Result<Archive, ()> {
-        let ptr = ffi::archive_ctor_file(path);
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Archive { ptr }),
+    /// Wrap an archive read from a single file descriptor, whose part is only known by range.
+    #[cfg(unix)]
+    fn from_ptr_fd(ptr: UniquePtr<ffi::Archive>, offset: u64) -> Archive {
+        let size = ffi::archive_getFilesize(ptr.as_ref().unwrap());
+        let part = ArchivePart {
+            path: None,
+            range: offset..offset + size,
+        };
+        Archive::from_ptr(ptr, vec![part])
+    }
+
+    pub fn new(path: &str) -> Result<Archive, Error> {
+        let parts = discover_parts(Path::new(path))?;
+        let ptr = ffi::archive_ctor_file(path)?;
+        Ok(Archive::from_ptr(ptr, parts))
+    }
+
+    /// Open the archive at `path` with explicit cache sizes and preloading, see [`OpenConfig`].
+    pub fn open_with_config<P: AsRef<Path>>(
+        path: P,
+        config: &OpenConfig,
+    ) -> Result<Archive, Error> {
+        let path = path.as_ref();
+        let parts = discover_parts(path)?;
+        let preload_dirents = match config.preload_dirents {
+            Some(nb_dirents) => i32::try_from(nb_dirents).map_err(|_| {
+                Error::InvalidArgument(format!("cannot preload {} dirents", nb_dirents))
+            })?,
+            None => -1,
+        };
+        let ptr = ffi::archive_ctor_path(&path_bytes(path)?, preload_dirents)?;
+        let archive = Archive::from_ptr(ptr, parts);
+        config.apply(&archive);
+        Ok(archive)
+    }
+
+    /// Open the archive from an already opened file descriptor.
+    ///
+    /// libzim duplicates the descriptor, so `fd` can be closed once this returns.
+    #[cfg(unix)]
+    pub fn from_fd<F: AsFd>(fd: F) -> Result<Archive, Error> {
+        let ptr = ffi::archive_ctor_fd(fd.as_fd().as_raw_fd())?;
+        Ok(Archive::from_ptr_fd(ptr, 0))
+    }
+
+    /// Open an archive stored as the `size` bytes starting at `offset` of a larger file,
+    /// as found in bundles and Android assets.
+    ///
+    /// libzim duplicates the descriptor, so `fd` can be closed once this returns.
+    #[cfg(unix)]
+    pub fn from_fd_range<F: AsFd>(fd: F, offset: u64, size: u64) -> Result<Archive, Error> {
+        let ptr = ffi::archive_ctor_fd_range(fd.as_fd().as_raw_fd(), offset, size)?;
+        Ok(Archive::from_ptr_fd(ptr, offset))
+    }
+
+    /// Open a split archive from its parts, in order, whatever their names or directories.
+    ///
+    /// Unlike [`Archive::new`], this does not rely on the `.zimaa`, `.zimab`, ... naming
+    /// convention to find the parts.
+    #[cfg(unix)]
+    pub fn open_parts<P: AsRef<Path>>(paths: &[P]) -> Result<Archive, Error> {
+        let files = paths
+            .iter()
+            .map(|path| {
+                let path = path.as_ref();
+                let file = std::fs::File::open(path)?;
+                let size = file.metadata()?.len();
+                Ok((file, size))
+            })
+            .collect::<Result<Vec<_>, io::Error>>()?;
+        let fds = files
+            .iter()
+            .map(|(file, size)| (file.as_fd(), 0, *size))
+            .collect::<Vec<_>>();
+        let ptr = Archive::ctor_fds(&fds)?;
+        let parts = paths
+            .iter()
+            .zip(files.iter())
+            .map(|(path, (_, size))| ArchivePart {
+                path: Some(path.as_ref().to_path_buf()),
+                range: 0..*size,
+            })
+            .collect();
+        Ok(Archive::from_ptr(ptr, parts))
+    }
+
+    /// Open a split archive from `(fd, offset, size)` parts, in order.
+    ///
+    /// libzim duplicates the descriptors, so they can be closed once this returns.
+    #[cfg(unix)]
+    pub fn open_part_fds(parts: &[(BorrowedFd<'_>, u64, u64)]) -> Result<Archive, Error> {
+        let ptr = Archive::ctor_fds(parts)?;
+        let parts = parts
+            .iter()
+            .map(|(_, offset, size)| ArchivePart {
+                path: None,
+                range: *offset..*offset + *size,
+            })
+            .collect();
+        Ok(Archive::from_ptr(ptr, parts))
+    }
+
+    #[cfg(unix)]
+    fn ctor_fds(parts: &[(BorrowedFd<'_>, u64, u64)]) -> Result<UniquePtr<ffi::Archive>, Error> {
+        if parts.is_empty() {
+            return Err(Error::InvalidArgument("no archive part given".to_string()));
         }
+        let fds = parts
+            .iter()
+            .map(|(fd, _, _)| fd.as_raw_fd())
+            .collect::<Vec<_>>();
+        let offsets = parts
+            .iter()
+            .map(|(_, offset, _)| *offset)
+            .collect::<Vec<_>>();
+        let sizes = parts.iter().map(|(_, _, size)| *size).collect::<Vec<_>>();
+        Ok(ffi::archive_ctor_fds(&fds, &offsets, &sizes)?)
     }
 
     pub fn get_filename(&self) -> String {
-        ffi::archive_getFilename(self.inner_ref()).to_string()
+        string_from_ptr(ffi::archive_getFilename(self.inner_ref()))
     }
 
     pub fn get_filesize(&self) -> u64 {
@@ -45,73 +230,114 @@ impl Archive {
         ffi::archive_getArticleCount(self.inner_ref())
     }
 
-    pub fn get_uuid(&self) -> Result<Uuid, ()> {
+    pub fn get_uuid(&self) -> Result<Uuid, Error> {
         Uuid::from_ptr(ffi::archive_getUuid(self.inner_ref()))
     }
 
-    pub fn get_metadata(&self, name: &str) -> Result<String, ()> {
-        string_from_ptr(ffi::archive_getMetadata(self.inner_ref(), name))
+    pub fn get_metadata(&self, name: &str) -> Result<String, Error> {
+        Ok(string_from_ptr(ffi::archive_getMetadata(
+            self.inner_ref(),
+            name,
+        )?))
     }
 
-    pub fn get_metadata_item(&self, name: &str) -> Result<Item, ()> {
-        Item::from_ptr(ffi::archive_getMetadataItem(self.inner_ref(), name))
+    pub fn get_metadata_item(&self, name: &str) -> Result<Item, Error> {
+        let item = ffi::archive_getMetadataItem(self.inner_ref(), name)?;
+        Ok(Item::from_ptr(item, self))
     }
 
-    pub fn get_metadata_keys(&self) -> Result<Vec<String>, ()> {
-        let keys = ffi::archive_getMetadataKeys(self.inner_ref());
-        vec_string_from_ptr(keys)
+    pub fn get_metadata_keys(&self) -> Result<Vec<String>, Error> {
+        let keys = ffi::archive_getMetadataKeys(self.inner_ref())?;
+        Ok(vec_string_from_ptr(keys))
     }
 
-    pub fn get_entry_bypath_index(&self, index: u32) -> Result<Entry, ()> {
-        Entry::from_ptr(ffi::archive_getEntryByPath_idx(self.inner_ref(), index))
+    /// Sizes (width and height, illustrations are square) of the illustrations
+    /// stored in the archive, in increasing order.
+    pub fn get_illustration_sizes(&self) -> Result<Vec<u32>, Error> {
+        let sizes = ffi::archive_getIllustrationSizes(self.inner_ref())?;
+        let mut sizes = sizes.iter().copied().collect::<Vec<u32>>();
+        sizes.sort_unstable();
+        Ok(sizes)
     }
 
-    pub fn get_entry_bypath_str(&self, path: &str) -> Result<Entry, ()> {
-        Entry::from_ptr(ffi::archive_getEntryByPath_str(self.inner_ref(), path))
+    /// Whether there is an illustration of this size, false as well if the archive
+    /// cannot be read.
+    pub fn has_illustration(&self, size: u32) -> bool {
+        ffi::archive_hasIllustration(self.inner_ref(), size).unwrap_or(false)
     }
 
-    pub fn get_entry_bytitle_index(&self, index: u32) -> Result<Entry, ()> {
-        Entry::from_ptr(ffi::archive_getEntryByTitle_idx(self.inner_ref(), index))
+    /// The PNG illustration of `size`x`size` pixels, 48 being the favicon every archive
+    /// should have.
+    pub fn get_illustration_item(&self, size: u32) -> Result<Item, Error> {
+        let item = ffi::archive_getIllustrationItem(self.inner_ref(), size)?;
+        Ok(Item::from_ptr(item, self))
     }
 
-    pub fn get_entry_bytitle_str(&self, title: &str) -> Result<Entry, ()> {
-        Entry::from_ptr(ffi::archive_getEntryByTitle_str(self.inner_ref(), title))
+    pub fn get_entry_bypath_index(&self, index: u32) -> Result<Entry, Error> {
+        let entry = ffi::archive_getEntryByPath_idx(self.inner_ref(), index)?;
+        Ok(Entry::from_ptr(entry, self))
     }
 
-    pub fn get_mainentry(&self) -> Result<Entry, ()> {
-        Entry::from_ptr(ffi::archive_getMainEntry(self.inner_ref()))
+    pub fn get_entry_bypath_str(&self, path: &str) -> Result<Entry, Error> {
+        let entry = ffi::archive_getEntryByPath_str(self.inner_ref(), path)?;
+        Ok(Entry::from_ptr(entry, self))
     }
 
-    pub fn get_randomentry(&self) -> Result<Entry, ()> {
-        Entry::from_ptr(ffi::archive_getRandomEntry(self.inner_ref()))
+    pub fn get_entry_bytitle_index(&self, index: u32) -> Result<Entry, Error> {
+        let entry = ffi::archive_getEntryByTitle_idx(self.inner_ref(), index)?;
+        Ok(Entry::from_ptr(entry, self))
     }
 
+    pub fn get_entry_bytitle_str(&self, title: &str) -> Result<Entry, Error> {
+        let entry = ffi::archive_getEntryByTitle_str(self.inner_ref(), title)?;
+        Ok(Entry::from_ptr(entry, self))
+    }
+
+    pub fn get_mainentry(&self) -> Result<Entry, Error> {
+        let entry = ffi::archive_getMainEntry(self.inner_ref())?;
+        Ok(Entry::from_ptr(entry, self))
+    }
+
+    pub fn get_randomentry(&self) -> Result<Entry, Error> {
+        let entry = ffi::archive_getRandomEntry(self.inner_ref())?;
+        Ok(Entry::from_ptr(entry, self))
+    }
+
+    /// Whether an entry has this path, false as well if the archive cannot be read.
     pub fn has_entry_bypath(&self, path: &str) -> bool {
-        ffi::archive_hasEntryByPath(self.inner_ref(), path)
+        ffi::archive_hasEntryByPath(self.inner_ref(), path).unwrap_or(false)
     }
 
+    /// Whether an entry has this title, false as well if the archive cannot be read.
     pub fn has_entry_bytitle(&self, title: &str) -> bool {
-        ffi::archive_hasEntryByTitle(self.inner_ref(), title)
+        ffi::archive_hasEntryByTitle(self.inner_ref(), title).unwrap_or(false)
     }
 
     pub fn has_mainentry(&self) -> bool {
         ffi::archive_hasMainEntry(self.inner_ref())
     }
 
+    /// Whether the archive has a usable full-text index, false as well if the index
+    /// cannot be read.
     pub fn has_fulltext_index(&self) -> bool {
-        ffi::archive_hasFulltextIndex(self.inner_ref())
+        ffi::archive_hasFulltextIndex(self.inner_ref()).unwrap_or(false)
     }
 
     pub fn has_checksum(&self) -> bool {
         ffi::archive_hasChecksum(self.inner_ref())
     }
 
-    pub fn get_checksum(&self) -> Result<String, ()> {
-        string_from_ptr(ffi::archive_getChecksum(self.inner_ref()))
+    pub fn get_checksum(&self) -> Result<String, Error> {
+        if !self.has_checksum() {
+            return Err(Error::EntryNotFound("archive has no checksum".to_string()));
+        }
+        Ok(string_from_ptr(ffi::archive_getChecksum(self.inner_ref())?))
     }
 
+    /// Whether the checksum of the archive matches its content. An archive that cannot
+    /// be read to the end does not match.
     pub fn check(&self) -> bool {
-        ffi::archive_check(self.inner_ref())
+        ffi::archive_check(self.inner_ref()).unwrap_or(false)
     }
 
     pub fn is_multipart(&self) -> bool {
@@ -122,68 +348,452 @@ impl Archive {
         ffi::archive_hasNewNamespaceScheme(self.inner_ref())
     }
 
-    pub fn iter_efficient(&self) -> Result<EntryRangeEfficient, ()> {
-        EntryRangeEfficient::from_ptr(ffi::archive_iterEfficient(self.inner_ref()))
+    /// Maximum number of dirents kept in memory.
+    pub fn get_dirent_cache_max_size(&self) -> u64 {
+        ffi::archive_getDirentCacheMaxSize(self.inner_ref())
+    }
+
+    /// Number of dirents currently kept in memory.
+    pub fn get_dirent_cache_current_size(&self) -> u64 {
+        ffi::archive_getDirentCacheCurrentSize(self.inner_ref())
     }
-}
 
+    /// Change the maximum number of cached dirents, evicting dirents if the cache shrinks.
+    pub fn set_dirent_cache_max_size(&self, nb_dirents: u64) {
+        ffi::archive_setDirentCacheMaxSize(self.inner_ref(), nb_dirents)
+    }
+
+    /// Number of dirents preloaded in the path lookup table.
+    pub fn get_dirent_lookup_cache_max_size(&self) -> u64 {
+        ffi::archive_getDirentLookupCacheMaxSize(self.inner_ref())
+    }
 
-pub struct EntryRangeEfficient {
-    ptr: UniquePtr<ffi::EntryRangeEfficient>,
+    /// All entries in cluster order, which is the fastest way to read every item.
+    pub fn iter_efficient(&self) -> Result<EntryRangeEfficient, Error> {
+        let range = ffi::archive_iterEfficient(self.inner_ref());
+        Ok(EntryRangeEfficient::from_ptr(range, self))
+    }
+
+    /// All entries sorted by path.
+    pub fn iter_by_path(&self) -> Result<EntryRangePath, Error> {
+        Ok(EntryRangePath::from_ptr(
+            ffi::archive_iterByPath(self.inner_ref()),
+            self,
+        ))
+    }
+
+    /// All entries sorted by title.
+    pub fn iter_by_title(&self) -> Result<EntryRangeTitle, Error> {
+        let range = ffi::archive_iterByTitle(self.inner_ref());
+        Ok(EntryRangeTitle::from_ptr(range, self))
+    }
+
+    /// Entries whose path starts with `prefix`, sorted by path.
+    pub fn find_by_path(&self, prefix: &str) -> Result<EntryRangePath, Error> {
+        let range = ffi::archive_findByPath(self.inner_ref(), prefix)?;
+        Ok(EntryRangePath::from_ptr(range, self))
+    }
+
+    /// Entries whose title starts with `prefix`, sorted by title.
+    pub fn find_by_title(&self, prefix: &str) -> Result<EntryRangeTitle, Error> {
+        let range = ffi::archive_findByTitle(self.inner_ref(), prefix)?;
+        Ok(EntryRangeTitle::from_ptr(range, self))
+    }
 }
 
-impl EntryRangeEfficient {
-    pub(crate) fn inner_ref(&self) -> &ffi::EntryRangeEfficient {
-        self.ptr.as_ref().unwrap()
+impl Archive {
+    /// Whether both handles refer to the same opened archive.
+    pub fn ptr_eq(&self, other: &Archive) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
     }
 
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::EntryRangeEfficient>) -> Result<EntryRangeEfficient, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(EntryRangeEfficient { ptr }),
-        }
+    /// The files this archive is read from, in order, with the bytes of each that hold
+    /// the archive. Single file archives have exactly one part.
+    pub fn parts(&self) -> &[ArchivePart] {
+        &self.inner.parts
     }
 
+    /// All metadata of the archive, parsed into typed fields.
+    pub fn metadata(&self) -> Result<ArchiveMetadata, Error> {
+        ArchiveMetadata::from_archive(self)
+    }
+
+    /// The `Tags` metadata.
+    pub fn tags(&self) -> Result<Tags, Error> {
+        let tags = self.get_metadata("Tags")?;
+        Ok(tags.parse().unwrap_or_default())
+    }
+
+    /// The `Counter` metadata.
+    pub fn counter(&self) -> Result<Counter, Error> {
+        self.get_metadata("Counter")?.parse()
+    }
+}
+
+/// One of the files an archive is read from, see [`Archive::parts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivePart {
+    pub(crate) path: Option<PathBuf>,
+    pub(crate) range: Range<u64>,
 }
 
-impl IntoIterator for EntryRangeEfficient {
-    type Item = Result<Entry, ()>;
-    type IntoIter = IterEfficient;
+impl ArchivePart {
+    /// Path of the file holding this part, `None` when it was opened from a file descriptor.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Bytes of the file that belong to the archive.
+    pub fn range(&self) -> Range<u64> {
+        self.range.clone()
+    }
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        IterEfficient {
-            current : ffi::entryrangeefficient_begin(self.inner_ref()),
-            end: ffi::entryrangeefficient_end(self.inner_ref())
+/// Find the parts libzim opens for `path`: the file itself, or else `<path>aa`, `<path>ab`, ...
+/// up to the first missing one.
+///
+/// Fails with the error of reading `path` itself when there is no part at all.
+pub(crate) fn discover_parts(path: &Path) -> io::Result<Vec<ArchivePart>> {
+    let part = |path: PathBuf, size: u64| ArchivePart {
+        path: Some(path),
+        range: 0..size,
+    };
+    let err = match std::fs::metadata(path) {
+        Ok(meta) => return Ok(vec![part(path.to_path_buf(), meta.len())]),
+        Err(err) => err,
+    };
+    let mut parts = Vec::new();
+    'parts: for first in 'a'..='z' {
+        for second in 'a'..='z' {
+            let mut name = path.as_os_str().to_owned();
+            name.push(format!("{}{}", first, second));
+            match std::fs::metadata(&name) {
+                Ok(meta) => parts.push(part(PathBuf::from(name), meta.len())),
+                Err(_) => break 'parts,
+            }
         }
     }
+    match parts.is_empty() {
+        true => Err(io::Error::new(
+            err.kind(),
+            format!("{}: {}", path.display(), err),
+        )),
+        false => Ok(parts),
+    }
 }
 
+/// Maximum size in bytes of the decompressed clusters kept in memory.
+///
+/// The cluster cache is shared by every open archive, as in libzim.
+pub fn get_cluster_cache_max_size() -> u64 {
+    #[cfg(not(feature = "pure-rust"))]
+    {
+        ffi::zim_getClusterCacheMaxSize()
+    }
+    #[cfg(feature = "pure-rust")]
+    {
+        crate::native::format::cluster_cache_max_size()
+    }
+}
+
+/// Size in bytes of the decompressed clusters currently kept in memory.
+pub fn get_cluster_cache_current_size() -> u64 {
+    #[cfg(not(feature = "pure-rust"))]
+    {
+        ffi::zim_getClusterCacheCurrentSize()
+    }
+    #[cfg(feature = "pure-rust")]
+    {
+        crate::native::format::cluster_cache_current_size()
+    }
+}
+
+/// Change the maximum size in bytes of the cluster cache, evicting clusters if it shrinks.
+pub fn set_cluster_cache_max_size(size_in_bytes: u64) {
+    #[cfg(not(feature = "pure-rust"))]
+    ffi::zim_setClusterCacheMaxSize(size_in_bytes);
+    #[cfg(feature = "pure-rust")]
+    crate::native::format::set_cluster_cache_max_size(size_in_bytes);
+}
 
-pub struct IterEfficient {
-    current : UniquePtr<ffi::IterEfficient>,
-    end : UniquePtr<ffi::IterEfficient>
+/// Options used by [`Archive::open_with_config`].
+///
+/// Every option left unset keeps libzim's default. The cluster cache is not per
+/// archive, see [`set_cluster_cache_max_size`].
+///
+/// ```no_run
+/// # use zim_rs::archive::{Archive, OpenConfig};
+/// let config = OpenConfig::new()
+///     .dirent_cache_size(512)
+///     .preload_dirents(1024);
+/// let archive = Archive::open_with_config("wikipedia.zim", &config).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenConfig {
+    dirent_cache_size: Option<u64>,
+    /// Read by both backends while opening, see [`OpenConfig::apply`].
+    pub(crate) preload_dirents: Option<u64>,
 }
 
-impl IterEfficient {
-    fn inner_current(&self) -> &ffi::IterEfficient {
-        self.current.as_ref().unwrap()
+impl OpenConfig {
+    pub fn new() -> OpenConfig {
+        OpenConfig::default()
     }
 
-    fn inner_end(&self) -> &ffi::IterEfficient {
-        self.end.as_ref().unwrap()
+    /// Maximum number of dirents cached by the archive.
+    pub fn dirent_cache_size(mut self, nb_dirents: u64) -> OpenConfig {
+        self.dirent_cache_size = Some(nb_dirents);
+        self
+    }
+
+    /// Preload the dirent lookup table with `nb_dirents` evenly spread entries, which
+    /// speeds up path lookups at the cost of reading them when the archive is opened.
+    ///
+    /// This is libzim's `OpenConfig::preloadDirentRanges`, so it only takes effect
+    /// through [`Archive::open_with_config`].
+    pub fn preload_dirents(mut self, nb_dirents: u64) -> OpenConfig {
+        self.preload_dirents = Some(nb_dirents);
+        self
+    }
+
+    /// Set the dirent cache size of a freshly opened archive. The lookup table is built
+    /// while opening, from [`OpenConfig::preload_dirents`].
+    pub(crate) fn apply(&self, archive: &Archive) {
+        if let Some(size) = self.dirent_cache_size {
+            archive.set_dirent_cache_max_size(size);
+        }
     }
 }
 
-impl Iterator for IterEfficient {
-    type Item = Result<Entry, ()>;
-    fn next(&mut self) -> Option<Self::Item> {
-        if !ffi::iterefficient_eq(self.inner_current(), self.inner_end()) {
-            let entry = Entry::from_ptr(ffi::iterefficient_star(self.inner_current()));
-            ffi::iterefficient_inc(self.current.pin_mut());
-            Some(entry)
-        } else {
-            None
+/// Raw bytes of `path`, so that non UTF-8 paths reach libzim untouched.
+#[cfg(not(feature = "pure-rust"))]
+pub(crate) fn path_bytes(path: &Path) -> Result<Cow<'_, [u8]>, Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(Cow::Borrowed(path.as_os_str().as_bytes()))
+    }
+    #[cfg(not(unix))]
+    {
+        match path.to_str() {
+            Some(path) => Ok(Cow::Borrowed(path.as_bytes())),
+            None => Err(Error::InvalidArgument(format!(
+                "{} is not valid unicode",
+                path.display()
+            ))),
         }
     }
 }
 
+/// Resolve `range` against a sequence of `len` elements into checked `(start, end)` positions.
+pub(crate) fn resolve_range<R: RangeBounds<usize>>(
+    range: R,
+    len: usize,
+) -> Result<(usize, usize), Error> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.saturating_add(1),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    match start <= end && end <= len {
+        true => Ok((start, end)),
+        false => Err(Error::InvalidArgument(format!(
+            "range {}..{} is out of bounds for length {}",
+            start, end, len
+        ))),
+    }
+}
+
+/// Declare a lazy range of entries and its iterator over one of libzim's
+/// `EntryRange<order>` instantiations; each order has its own set of bindings.
+///
+/// Positions are plain indexes into the range, so sizing, skipping, slicing and
+/// reverse iteration never walk through the entries in between.
+#[cfg(not(feature = "pure-rust"))]
+macro_rules! entry_range {
+    (
+        $(#[$meta:meta])*
+        $range:ident, $iter:ident, $ffi_range:ident, $ffi_iter:ident,
+        $size:ident, $offset:ident, $begin:ident, $begin_at:ident, $entry_at:ident,
+        $inc:ident, $star:ident
+    ) => {
+        $(#[$meta])*
+        pub struct $range {
+            ptr: UniquePtr<ffi::$ffi_range>,
+            archive: Archive,
+        }
+
+        impl $range {
+            pub(crate) fn inner_ref(&self) -> &ffi::$ffi_range {
+                self.ptr.as_ref().unwrap()
+            }
+
+            pub(crate) fn from_ptr(ptr: UniquePtr<ffi::$ffi_range>, archive: &Archive) -> $range {
+                $range {
+                    ptr,
+                    archive: archive.clone(),
+                }
+            }
+
+            pub fn len(&self) -> usize {
+                ffi::$size(self.inner_ref()) as usize
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// The entries at positions `range` of this range, in the same order.
+            pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Result<$range, Error> {
+                let (start, end) = resolve_range(range, self.len())?;
+                let range = ffi::$offset(self.inner_ref(), start as u32, (end - start) as u32);
+                Ok($range::from_ptr(range, &self.archive))
+            }
+        }
+
+        impl IntoIterator for $range {
+            type Item = Result<Entry, Error>;
+            type IntoIter = $iter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                $iter {
+                    current: ffi::$begin(self.inner_ref()),
+                    front: 0,
+                    back: self.len(),
+                    range: self.ptr,
+                    archive: self.archive,
+                }
+            }
+        }
+
+        pub struct $iter {
+            range: UniquePtr<ffi::$ffi_range>,
+            /// Points at `front`, so that plain forward iteration is a single increment.
+            current: UniquePtr<ffi::$ffi_iter>,
+            front: usize,
+            back: usize,
+            archive: Archive,
+        }
+
+        impl $iter {
+            fn inner_range(&self) -> &ffi::$ffi_range {
+                self.range.as_ref().unwrap()
+            }
+
+            fn inner_current(&self) -> &ffi::$ffi_iter {
+                self.current.as_ref().unwrap()
+            }
+
+            fn entry_at(&self, pos: usize) -> Result<Entry, Error> {
+                let entry = ffi::$entry_at(self.inner_range(), pos as u32)?;
+                Ok(Entry::from_ptr(entry, &self.archive))
+            }
+        }
+
+        impl Iterator for $iter {
+            type Item = Result<Entry, Error>;
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.front < self.back {
+                    let entry = ffi::$star(self.inner_current())
+                        .map(|entry| Entry::from_ptr(entry, &self.archive))
+                        .map_err(Error::from);
+                    ffi::$inc(self.current.pin_mut());
+                    self.front += 1;
+                    Some(entry)
+                } else {
+                    None
+                }
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.back - self.front;
+                (len, Some(len))
+            }
+
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                if n >= self.len() {
+                    self.front = self.back;
+                    return None;
+                }
+                self.current = ffi::$begin_at(self.inner_range(), (self.front + n) as u32);
+                self.front += n;
+                self.next()
+            }
+        }
+
+        impl DoubleEndedIterator for $iter {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                if self.front < self.back {
+                    self.back -= 1;
+                    Some(self.entry_at(self.back))
+                } else {
+                    None
+                }
+            }
+
+            fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+                if n >= self.len() {
+                    self.back = self.front;
+                    return None;
+                }
+                self.back -= n;
+                self.next_back()
+            }
+        }
+
+        impl ExactSizeIterator for $iter {}
+    };
+}
+
+#[cfg(not(feature = "pure-rust"))]
+entry_range!(
+    /// Entries in cluster order, see [`Archive::iter_efficient`].
+    EntryRangeEfficient,
+    IterEfficient,
+    EntryRangeEfficient,
+    IterEfficient,
+    entryrangeefficient_size,
+    entryrangeefficient_offset,
+    entryrangeefficient_begin,
+    entryrangeefficient_begin_at,
+    entryrangeefficient_entry_at,
+    iterefficient_inc,
+    iterefficient_star
+);
+
+#[cfg(not(feature = "pure-rust"))]
+entry_range!(
+    /// Entries in path order, see [`Archive::iter_by_path`] and [`Archive::find_by_path`].
+    EntryRangePath,
+    IterPath,
+    EntryRangePath,
+    IterPath,
+    entryrangepath_size,
+    entryrangepath_offset,
+    entryrangepath_begin,
+    entryrangepath_begin_at,
+    entryrangepath_entry_at,
+    iterpath_inc,
+    iterpath_star
+);
+
+#[cfg(not(feature = "pure-rust"))]
+entry_range!(
+    /// Entries in title order, see [`Archive::iter_by_title`] and [`Archive::find_by_title`].
+    EntryRangeTitle,
+    IterTitle,
+    EntryRangeTitle,
+    IterTitle,
+    entryrangetitle_size,
+    entryrangetitle_offset,
+    entryrangetitle_begin,
+    entryrangetitle_begin_at,
+    entryrangetitle_entry_at,
+    itertitle_inc,
+    itertitle_star
+);
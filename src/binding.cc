@@ -0,0 +1,667 @@
+#include "zim-rs/src/binding.h"
+#include "zim-rs/src/binding.rs.h"
+
+#include <string>
+#include <vector>
+
+namespace zim_rs {
+
+namespace {
+
+std::string to_string(rust::Str s) { return std::string(s.data(), s.size()); }
+
+std::string to_string(rust::Slice<const uint8_t> s) {
+  return std::string(reinterpret_cast<const char *>(s.data()), s.size());
+}
+
+template <typename T> std::unique_ptr<T> boxed(T &&value) {
+  return std::make_unique<T>(std::forward<T>(value));
+}
+
+template <typename Range>
+auto begin_at(const Range &r, uint32_t start) {
+  return boxed(r.offset(start, r.size() - start).begin());
+}
+
+template <typename Range>
+std::unique_ptr<Entry> entry_at(const Range &r, uint32_t pos) {
+  if (pos >= r.size()) {
+    throw std::out_of_range("entry " + std::to_string(pos) +
+                            " is out of the range");
+  }
+  return std::make_unique<Entry>(*r.offset(pos, 1).begin());
+}
+
+} // namespace
+
+std::unique_ptr<Archive> archive_ctor_file(rust::Str path) {
+  return std::make_unique<Archive>(to_string(path));
+}
+
+std::unique_ptr<Archive> archive_ctor_path(rust::Slice<const uint8_t> path,
+                                           int32_t preload_dirent_ranges) {
+  zim::OpenConfig config;
+  if (preload_dirent_ranges >= 0) {
+    config.preloadDirentRanges(preload_dirent_ranges);
+  }
+  return std::make_unique<Archive>(to_string(path), config);
+}
+
+std::unique_ptr<Archive> archive_ctor_fd(int32_t fd) {
+  return std::make_unique<Archive>(fd);
+}
+
+std::unique_ptr<Archive> archive_ctor_fd_range(int32_t fd, uint64_t offset,
+                                               uint64_t size) {
+  return std::make_unique<Archive>(zim::FdInput(fd, offset, size));
+}
+
+std::unique_ptr<Archive> archive_ctor_fds(rust::Slice<const int32_t> fds,
+                                          rust::Slice<const uint64_t> offsets,
+                                          rust::Slice<const uint64_t> sizes) {
+  std::vector<zim::FdInput> inputs;
+  for (size_t i = 0; i < fds.size(); i++) {
+    inputs.emplace_back(fds[i], offsets[i], sizes[i]);
+  }
+  return std::make_unique<Archive>(inputs);
+}
+
+bool archive_check(const Archive &a) { return a.check(); }
+
+uint32_t archive_getAllEntryCount(const Archive &a) {
+  return a.getAllEntryCount();
+}
+
+uint32_t archive_getArticleCount(const Archive &a) {
+  return a.getArticleCount();
+}
+
+uint32_t archive_getEntryCount(const Archive &a) { return a.getEntryCount(); }
+
+std::unique_ptr<std::string> archive_getChecksum(const Archive &a) {
+  return boxed(a.getChecksum());
+}
+
+std::unique_ptr<Entry> archive_getEntryByPath_idx(const Archive &a,
+                                                  uint32_t idx) {
+  return boxed(a.getEntryByPath(idx));
+}
+
+std::unique_ptr<Entry> archive_getEntryByPath_str(const Archive &a,
+                                                  rust::Str path) {
+  return boxed(a.getEntryByPath(to_string(path)));
+}
+
+std::unique_ptr<Entry> archive_getEntryByTitle_idx(const Archive &a,
+                                                   uint32_t idx) {
+  return boxed(a.getEntryByTitle(idx));
+}
+
+std::unique_ptr<Entry> archive_getEntryByTitle_str(const Archive &a,
+                                                   rust::Str title) {
+  return boxed(a.getEntryByTitle(to_string(title)));
+}
+
+std::unique_ptr<std::string> archive_getFilename(const Archive &a) {
+  return std::make_unique<std::string>(a.getFilename());
+}
+
+uint64_t archive_getFilesize(const Archive &a) { return a.getFilesize(); }
+
+std::unique_ptr<Entry> archive_getMainEntry(const Archive &a) {
+  return boxed(a.getMainEntry());
+}
+
+std::unique_ptr<Entry> archive_getRandomEntry(const Archive &a) {
+  return boxed(a.getRandomEntry());
+}
+
+std::unique_ptr<std::string> archive_getMetadata(const Archive &a,
+                                                 rust::Str name) {
+  return boxed(a.getMetadata(to_string(name)));
+}
+
+std::unique_ptr<Item> archive_getMetadataItem(const Archive &a,
+                                              rust::Str name) {
+  return boxed(a.getMetadataItem(to_string(name)));
+}
+
+std::unique_ptr<std::vector<std::string>>
+archive_getMetadataKeys(const Archive &a) {
+  return boxed(a.getMetadataKeys());
+}
+
+std::unique_ptr<std::vector<uint32_t>>
+archive_getIllustrationSizes(const Archive &a) {
+  auto sizes = a.getIllustrationSizes();
+  return std::make_unique<std::vector<uint32_t>>(sizes.begin(), sizes.end());
+}
+
+bool archive_hasIllustration(const Archive &a, uint32_t size) {
+  return a.hasIllustration(size);
+}
+
+std::unique_ptr<Item> archive_getIllustrationItem(const Archive &a,
+                                                  uint32_t size) {
+  return boxed(a.getIllustrationItem(size));
+}
+
+std::unique_ptr<Uuid> archive_getUuid(const Archive &a) {
+  return boxed(a.getUuid());
+}
+
+bool archive_hasChecksum(const Archive &a) { return a.hasChecksum(); }
+
+bool archive_hasEntryByPath(const Archive &a, rust::Str path) {
+  return a.hasEntryByPath(to_string(path));
+}
+
+bool archive_hasEntryByTitle(const Archive &a, rust::Str title) {
+  return a.hasEntryByTitle(to_string(title));
+}
+
+bool archive_hasFulltextIndex(const Archive &a) {
+  return a.hasFulltextIndex();
+}
+
+bool archive_hasMainEntry(const Archive &a) { return a.hasMainEntry(); }
+
+bool archive_hasNewNamespaceScheme(const Archive &a) {
+  return a.hasNewNamespaceScheme();
+}
+
+bool archive_isMultiPart(const Archive &a) { return a.isMultiPart(); }
+
+uint64_t zim_getClusterCacheMaxSize() { return zim::getClusterCacheMaxSize(); }
+
+uint64_t zim_getClusterCacheCurrentSize() {
+  return zim::getClusterCacheCurrentSize();
+}
+
+void zim_setClusterCacheMaxSize(uint64_t size_in_bytes) {
+  zim::setClusterCacheMaxSize(size_in_bytes);
+}
+
+uint64_t archive_getDirentCacheMaxSize(const Archive &a) {
+  return a.getDirentCacheMaxSize();
+}
+
+uint64_t archive_getDirentCacheCurrentSize(const Archive &a) {
+  return a.getDirentCacheCurrentSize();
+}
+
+// Copies of an archive share its dirent cache, so resizing the cache of a copy
+// resizes the cache of `a`.
+void archive_setDirentCacheMaxSize(const Archive &a, uint64_t nb_dirents) {
+  Archive copy(a);
+  copy.setDirentCacheMaxSize(nb_dirents);
+}
+
+uint64_t archive_getDirentLookupCacheMaxSize(const Archive &a) {
+  return a.getDirentLookupCacheMaxSize();
+}
+
+std::unique_ptr<EntryRangeEfficient> archive_iterEfficient(const Archive &a) {
+  return boxed(a.iterEfficient());
+}
+
+std::unique_ptr<EntryRangePath> archive_iterByPath(const Archive &a) {
+  return boxed(a.iterByPath());
+}
+
+std::unique_ptr<EntryRangeTitle> archive_iterByTitle(const Archive &a) {
+  return boxed(a.iterByTitle());
+}
+
+std::unique_ptr<EntryRangePath> archive_findByPath(const Archive &a,
+                                                   rust::Str path) {
+  return boxed(a.findByPath(to_string(path)));
+}
+
+std::unique_ptr<EntryRangeTitle> archive_findByTitle(const Archive &a,
+                                                     rust::Str title) {
+  return boxed(a.findByTitle(to_string(title)));
+}
+
+std::unique_ptr<IterEfficient>
+entryrangeefficient_begin(const EntryRangeEfficient &r) {
+  return boxed(r.begin());
+}
+
+uint32_t entryrangeefficient_size(const EntryRangeEfficient &r) {
+  return r.size();
+}
+
+std::unique_ptr<EntryRangeEfficient>
+entryrangeefficient_offset(const EntryRangeEfficient &r, uint32_t start,
+                           uint32_t max) {
+  return boxed(r.offset(start, max));
+}
+
+std::unique_ptr<IterEfficient>
+entryrangeefficient_begin_at(const EntryRangeEfficient &r, uint32_t start) {
+  return begin_at(r, start);
+}
+
+std::unique_ptr<Entry> entryrangeefficient_entry_at(const EntryRangeEfficient &r,
+                                               uint32_t pos) {
+  return entry_at(r, pos);
+}
+
+void iterefficient_inc(IterEfficient &it) { ++it; }
+
+std::unique_ptr<Entry> iterefficient_star(const IterEfficient &it) {
+  return std::make_unique<Entry>(*it);
+}
+
+std::unique_ptr<IterPath> entryrangepath_begin(const EntryRangePath &r) {
+  return boxed(r.begin());
+}
+
+uint32_t entryrangepath_size(const EntryRangePath &r) { return r.size(); }
+
+std::unique_ptr<EntryRangePath>
+entryrangepath_offset(const EntryRangePath &r, uint32_t start, uint32_t max) {
+  return boxed(r.offset(start, max));
+}
+
+std::unique_ptr<IterPath>
+entryrangepath_begin_at(const EntryRangePath &r, uint32_t start) {
+  return begin_at(r, start);
+}
+
+std::unique_ptr<Entry> entryrangepath_entry_at(const EntryRangePath &r,
+                                               uint32_t pos) {
+  return entry_at(r, pos);
+}
+
+void iterpath_inc(IterPath &it) { ++it; }
+
+std::unique_ptr<Entry> iterpath_star(const IterPath &it) {
+  return std::make_unique<Entry>(*it);
+}
+
+std::unique_ptr<IterTitle> entryrangetitle_begin(const EntryRangeTitle &r) {
+  return boxed(r.begin());
+}
+
+uint32_t entryrangetitle_size(const EntryRangeTitle &r) { return r.size(); }
+
+std::unique_ptr<EntryRangeTitle>
+entryrangetitle_offset(const EntryRangeTitle &r, uint32_t start, uint32_t max) {
+  return boxed(r.offset(start, max));
+}
+
+std::unique_ptr<IterTitle>
+entryrangetitle_begin_at(const EntryRangeTitle &r, uint32_t start) {
+  return begin_at(r, start);
+}
+
+std::unique_ptr<Entry> entryrangetitle_entry_at(const EntryRangeTitle &r,
+                                               uint32_t pos) {
+  return entry_at(r, pos);
+}
+
+void itertitle_inc(IterTitle &it) { ++it; }
+
+std::unique_ptr<Entry> itertitle_star(const IterTitle &it) {
+  return std::make_unique<Entry>(*it);
+}
+
+const char *blob_data(const Blob &b) { return b.data(); }
+
+uint64_t blob_size(const Blob &b) { return b.size(); }
+
+uint32_t entry_getIndex(const Entry &e) { return e.getIndex(); }
+
+std::unique_ptr<Item> entry_getItem(const Entry &e, bool follow) {
+  return boxed(e.getItem(follow));
+}
+
+std::unique_ptr<std::string> entry_getPath(const Entry &e) {
+  return boxed(e.getPath());
+}
+
+std::unique_ptr<std::string> entry_getTitle(const Entry &e) {
+  return boxed(e.getTitle());
+}
+
+std::unique_ptr<Item> entry_getRedirect(const Entry &e) {
+  return boxed(e.getRedirect());
+}
+
+std::unique_ptr<Entry> entry_getRedirectEntry(const Entry &e) {
+  return boxed(e.getRedirectEntry());
+}
+
+bool entry_isRedirect(const Entry &e) { return e.isRedirect(); }
+
+std::unique_ptr<Blob> item_getData(const Item &i) { return boxed(i.getData()); }
+
+std::unique_ptr<Blob> item_getData_offset(const Item &i, uint64_t offset,
+                                          uint64_t size) {
+  return boxed(i.getData(offset, size));
+}
+
+uint32_t item_getIndex(const Item &i) { return i.getIndex(); }
+
+std::unique_ptr<std::string> item_getMimetype(const Item &i) {
+  return boxed(i.getMimetype());
+}
+
+std::unique_ptr<std::string> item_getPath(const Item &i) {
+  return boxed(i.getPath());
+}
+
+std::unique_ptr<std::string> item_getTitle(const Item &i) {
+  return boxed(i.getTitle());
+}
+
+uint64_t item_getSize(const Item &i) { return i.getSize(); }
+
+std::unique_ptr<std::string> item_getDirectAccessPath(const Item &i) {
+  auto info = i.getDirectAccessInformation();
+  return boxed(info.isValid() ? std::move(info.filename) : std::string());
+}
+
+uint64_t item_getDirectAccessOffset(const Item &i) {
+  return i.getDirectAccessInformation().offset;
+}
+
+std::unique_ptr<Query> query_ctor(rust::Str query) {
+  return std::make_unique<Query>(to_string(query));
+}
+
+void query_setQuery(Query &q, rust::Str query) { q.setQuery(to_string(query)); }
+
+void query_setGeorange(Query &q, float latitude, float longitude,
+                       float distance) {
+  q.setGeorange(latitude, longitude, distance);
+}
+
+std::unique_ptr<Searcher> searcher_ctor(const Archive &a) {
+  return std::make_unique<Searcher>(a);
+}
+
+void searcher_addArchive(Searcher &s, const Archive &a) { s.addArchive(a); }
+
+std::unique_ptr<Search> searcher_search(Searcher &s, const Query &q) {
+  return boxed(s.search(q));
+}
+
+void searcher_setVerbose(Searcher &s, bool verbose) { s.setVerbose(verbose); }
+
+int32_t search_getEstimatedMatches(const Search &s) {
+  return s.getEstimatedMatches();
+}
+
+std::unique_ptr<SearchResultSet>
+search_getResults(const Search &s, int32_t start, int32_t max_results) {
+  return boxed(s.getResults(start, max_results));
+}
+
+std::unique_ptr<SearchIterator> searchresultset_begin(const SearchResultSet &s) {
+  return boxed(s.begin());
+}
+
+std::unique_ptr<SearchIterator> searchresultset_end(const SearchResultSet &s) {
+  return boxed(s.end());
+}
+
+int32_t searchresultset_size(const SearchResultSet &s) { return s.size(); }
+
+bool searchiterator_operator_eq(const SearchIterator &a,
+                                const SearchIterator &b) {
+  return a == b;
+}
+
+void searchiterator_operator_inc(SearchIterator &it) { ++it; }
+
+void searchiterator_operator_dec(SearchIterator &it) { --it; }
+
+std::unique_ptr<Entry> searchiterator_operator_star(const SearchIterator &it) {
+  return std::make_unique<Entry>(*it);
+}
+
+int32_t searchiterator_getScore(const SearchIterator &it) {
+  return it.getScore();
+}
+
+std::unique_ptr<std::string>
+searchiterator_getSnippet(const SearchIterator &it) {
+  return boxed(it.getSnippet());
+}
+
+int32_t searchiterator_getWordCount(const SearchIterator &it) {
+  return it.getWordCount();
+}
+
+int32_t searchiterator_getSize(const SearchIterator &it) {
+  return it.getSize();
+}
+
+int32_t searchiterator_getFileIndex(const SearchIterator &it) {
+  return it.getFileIndex();
+}
+
+std::unique_ptr<Uuid> searchiterator_getZimId(const SearchIterator &it) {
+  return boxed(it.getZimId());
+}
+
+std::unique_ptr<SuggestionSearcher> suggestionsearcher_ctor(const Archive &a) {
+  return std::make_unique<SuggestionSearcher>(a);
+}
+
+void suggestionsearcher_setVerbose(SuggestionSearcher &s, bool verbose) {
+  s.setVerbose(verbose);
+}
+
+std::unique_ptr<SuggestionSearch>
+suggestionsearcher_suggest(SuggestionSearcher &s, rust::Str query) {
+  return boxed(s.suggest(to_string(query)));
+}
+
+int32_t suggestionsearch_getEstimatedMatches(const SuggestionSearch &s) {
+  return s.getEstimatedMatches();
+}
+
+std::unique_ptr<SuggestionResultSet>
+suggestionsearch_getResults(const SuggestionSearch &s, int32_t start,
+                            int32_t max_results) {
+  return std::make_unique<SuggestionResultSet>(s.getResults(start, max_results));
+}
+
+std::unique_ptr<SuggestionIterator>
+suggestionresultset_begin(const SuggestionResultSet &s) {
+  return boxed(s.begin());
+}
+
+std::unique_ptr<SuggestionIterator>
+suggestionresultset_end(const SuggestionResultSet &s) {
+  return boxed(s.end());
+}
+
+int32_t suggestionresultset_size(const SuggestionResultSet &s) {
+  return s.size();
+}
+
+bool suggestioniterator_operator_eq(const SuggestionIterator &a,
+                                    const SuggestionIterator &b) {
+  return a == b;
+}
+
+void suggestioniterator_operator_inc(SuggestionIterator &it) { ++it; }
+
+void suggestioniterator_operator_dec(SuggestionIterator &it) { --it; }
+
+std::unique_ptr<SuggestionItem>
+suggestioniterator_operator_star(SuggestionIterator &it) {
+  return std::make_unique<SuggestionItem>(*it);
+}
+
+std::unique_ptr<std::string> suggestionitem_getPath(const SuggestionItem &s) {
+  return boxed(s.getPath());
+}
+
+std::unique_ptr<std::string> suggestionitem_getTitle(const SuggestionItem &s) {
+  return boxed(s.getTitle());
+}
+
+std::unique_ptr<std::string>
+suggestionitem_getSnippet(const SuggestionItem &s) {
+  return boxed(s.getSnippet());
+}
+
+bool suggestionitem_hasSnippet(const SuggestionItem &s) {
+  return s.hasSnippet();
+}
+
+std::unique_ptr<Uuid> uuid_generate(rust::Str value) {
+  return boxed(Uuid::generate(to_string(value)));
+}
+
+std::unique_ptr<std::string> uuid_std_string(const Uuid &u) {
+  return std::make_unique<std::string>(static_cast<std::string>(u));
+}
+
+RustItem::RustItem(rust::Box<WriterItemBox> item) : item(std::move(item)) {}
+
+std::string RustItem::getPath() const {
+  return std::string(writer_item_path(*item));
+}
+
+std::string RustItem::getTitle() const {
+  return std::string(writer_item_title(*item));
+}
+
+std::string RustItem::getMimeType() const {
+  return std::string(writer_item_mimetype(*item));
+}
+
+zim::writer::Hints RustItem::getHints() const {
+  zim::writer::Hints hints;
+  for (auto key : {zim::writer::COMPRESS, zim::writer::FRONT_ARTICLE}) {
+    int64_t value = writer_item_hint(*item, static_cast<uint8_t>(key));
+    if (value >= 0) {
+      hints[key] = static_cast<uint64_t>(value);
+    }
+  }
+  return hints;
+}
+
+std::unique_ptr<zim::writer::ContentProvider>
+RustItem::getContentProvider() const {
+  return std::make_unique<RustContentProvider>(
+      writer_item_content_provider(*item));
+}
+
+std::shared_ptr<zim::writer::IndexData> RustItem::getIndexData() const {
+  auto data = writer_item_index_data(*item);
+  if (index_data_is_default(*data)) {
+    return zim::writer::Item::getIndexData();
+  }
+  return std::make_shared<RustIndexData>(std::move(data));
+}
+
+RustContentProvider::RustContentProvider(rust::Box<ContentProviderBox> provider)
+    : provider(std::move(provider)) {}
+
+zim::size_type RustContentProvider::getSize() const {
+  return content_provider_size(*provider);
+}
+
+zim::Blob RustContentProvider::feed() {
+  chunk = content_provider_feed(*provider);
+  return zim::Blob(reinterpret_cast<const char *>(chunk.data()), chunk.size());
+}
+
+RustIndexData::RustIndexData(rust::Box<IndexDataBox> data)
+    : data(std::move(data)) {}
+
+bool RustIndexData::hasIndexData() const {
+  return index_data_has_index_data(*data);
+}
+
+std::string RustIndexData::getTitle() const {
+  return std::string(index_data_title(*data));
+}
+
+std::string RustIndexData::getContent() const {
+  return std::string(index_data_content(*data));
+}
+
+std::string RustIndexData::getKeywords() const {
+  return std::string(index_data_keywords(*data));
+}
+
+uint32_t RustIndexData::getWordCount() const {
+  return index_data_word_count(*data);
+}
+
+zim::writer::IndexData::GeoPosition RustIndexData::getGeoPosition() const {
+  auto position = index_data_geo_position(*data);
+  return std::make_tuple(position.valid, position.latitude, position.longitude);
+}
+
+std::unique_ptr<Creator> creator_ctor() { return std::make_unique<Creator>(); }
+
+void creator_configVerbose(Creator &c, bool verbose) {
+  c.configVerbose(verbose);
+}
+
+void creator_configCompression(Creator &c, uint8_t compression) {
+  c.configCompression(static_cast<zim::Compression>(compression));
+}
+
+void creator_configClusterSize(Creator &c, uint64_t size) {
+  c.configClusterSize(size);
+}
+
+void creator_configIndexing(Creator &c, bool indexing, rust::Str language) {
+  c.configIndexing(indexing, to_string(language));
+}
+
+void creator_configNbWorkers(Creator &c, uint32_t nb_workers) {
+  c.configNbWorkers(nb_workers);
+}
+
+void creator_startZimCreation(Creator &c, rust::Slice<const uint8_t> path) {
+  c.startZimCreation(to_string(path));
+}
+
+void creator_addItem(Creator &c, rust::Box<WriterItemBox> item) {
+  c.addItem(std::make_shared<RustItem>(std::move(item)));
+}
+
+void creator_addRedirection(Creator &c, rust::Str path, rust::Str title,
+                            rust::Str target, int64_t front_article) {
+  zim::writer::Hints hints;
+  if (front_article >= 0) {
+    hints[zim::writer::FRONT_ARTICLE] = static_cast<uint64_t>(front_article);
+  }
+  c.addRedirection(to_string(path), to_string(title), to_string(target), hints);
+}
+
+void creator_addAlias(Creator &c, rust::Str path, rust::Str title,
+                      rust::Str target) {
+  c.addAlias(to_string(path), to_string(title), to_string(target));
+}
+
+void creator_addMetadata(Creator &c, rust::Str name,
+                         rust::Slice<const uint8_t> content,
+                         rust::Str mimetype) {
+  c.addMetadata(to_string(name), to_string(content), to_string(mimetype));
+}
+
+void creator_addIllustration(Creator &c, uint32_t size,
+                             rust::Slice<const uint8_t> content) {
+  c.addIllustration(size, to_string(content));
+}
+
+void creator_setMainPath(Creator &c, rust::Str path) {
+  c.setMainPath(to_string(path));
+}
+
+void creator_finishZimCreation(Creator &c) { c.finishZimCreation(); }
+
+} // namespace zim_rs
@@ -0,0 +1,290 @@
+// C++ side of the cxx bridge declared in binding.rs.
+//
+// Functions are thin wrappers around libzim's API, named `<class>_<method>`, which
+// copy whatever libzim returns by value into a `std::unique_ptr`.
+
+#pragma once
+
+#include <cstdint>
+#include <exception>
+#include <memory>
+#include <stdexcept>
+#include <string>
+#include <system_error>
+
+#include <zim/archive.h>
+#include <zim/blob.h>
+#include <zim/entry.h>
+#include <zim/error.h>
+#include <zim/item.h>
+#include <zim/search.h>
+#include <zim/suggestion.h>
+#include <zim/uuid.h>
+#include <zim/writer/contentProvider.h>
+#include <zim/writer/creator.h>
+#include <zim/writer/item.h>
+
+#include "rust/cxx.h"
+
+namespace zim_rs {
+
+// Separates the kind of exception from its message in the `what()` of the
+// exceptions forwarded to Rust, see `From<cxx::Exception> for Error`.
+const char KIND_SEPARATOR = '\x1f';
+
+inline std::string tag_exception(const char *kind, const char *what) {
+  return std::string(kind) + KIND_SEPARATOR + what;
+}
+
+} // namespace zim_rs
+
+namespace rust {
+namespace behavior {
+
+// Called by cxx around every binding declared with a `Result`. cxx only forwards
+// `what()`, so tag it with the kind of exception libzim threw.
+template <typename Try, typename Fail>
+static void trycatch(Try &&func, Fail &&fail) noexcept try {
+  func();
+} catch (const zim::EntryNotFound &e) {
+  fail(zim_rs::tag_exception("EntryNotFound", e.what()).c_str());
+} catch (const zim::ZimFileFormatError &e) {
+  fail(zim_rs::tag_exception("ZimFileFormat", e.what()).c_str());
+} catch (const zim::InvalidType &e) {
+  fail(zim_rs::tag_exception("InvalidArgument", e.what()).c_str());
+} catch (const std::invalid_argument &e) {
+  fail(zim_rs::tag_exception("InvalidArgument", e.what()).c_str());
+} catch (const std::out_of_range &e) {
+  fail(zim_rs::tag_exception("InvalidArgument", e.what()).c_str());
+} catch (const std::system_error &e) {
+  fail(zim_rs::tag_exception("Io", e.what()).c_str());
+} catch (const std::exception &e) {
+  fail(zim_rs::tag_exception("Libzim", e.what()).c_str());
+} catch (...) {
+  fail(zim_rs::tag_exception("Libzim", "unknown exception").c_str());
+}
+
+} // namespace behavior
+} // namespace rust
+
+namespace zim_rs {
+
+using zim::Archive;
+using zim::Blob;
+using zim::Entry;
+using zim::Item;
+using zim::Query;
+using zim::Search;
+using zim::Searcher;
+using zim::SearchIterator;
+using zim::SearchResultSet;
+using zim::SuggestionItem;
+using zim::SuggestionIterator;
+using zim::SuggestionResultSet;
+using zim::SuggestionSearch;
+using zim::SuggestionSearcher;
+using zim::Uuid;
+using zim::writer::Creator;
+
+using EntryRangeEfficient = Archive::EntryRange<zim::EntryOrder::efficientOrder>;
+using IterEfficient = Archive::iterator<zim::EntryOrder::efficientOrder>;
+using EntryRangePath = Archive::EntryRange<zim::EntryOrder::pathOrder>;
+using IterPath = Archive::iterator<zim::EntryOrder::pathOrder>;
+using EntryRangeTitle = Archive::EntryRange<zim::EntryOrder::titleOrder>;
+using IterTitle = Archive::iterator<zim::EntryOrder::titleOrder>;
+
+struct WriterItemBox;
+struct ContentProviderBox;
+struct IndexDataBox;
+
+// A `zim::writer::Item` implemented by a Rust `WriterItem`.
+class RustItem : public zim::writer::Item {
+public:
+  explicit RustItem(rust::Box<WriterItemBox> item);
+  std::string getPath() const override;
+  std::string getTitle() const override;
+  std::string getMimeType() const override;
+  zim::writer::Hints getHints() const override;
+  std::unique_ptr<zim::writer::ContentProvider> getContentProvider() const override;
+  std::shared_ptr<zim::writer::IndexData> getIndexData() const override;
+
+private:
+  rust::Box<WriterItemBox> item;
+};
+
+// A `zim::writer::ContentProvider` implemented by a Rust `ContentProvider`.
+class RustContentProvider : public zim::writer::ContentProvider {
+public:
+  explicit RustContentProvider(rust::Box<ContentProviderBox> provider);
+  zim::size_type getSize() const override;
+  zim::Blob feed() override;
+
+private:
+  rust::Box<ContentProviderBox> provider;
+  // The blob returned by `feed` points into it, libzim reads it before feeding again.
+  rust::Vec<uint8_t> chunk;
+};
+
+// A `zim::writer::IndexData` implemented by a Rust `IndexData`.
+class RustIndexData : public zim::writer::IndexData {
+public:
+  explicit RustIndexData(rust::Box<IndexDataBox> data);
+  bool hasIndexData() const override;
+  std::string getTitle() const override;
+  std::string getContent() const override;
+  std::string getKeywords() const override;
+  uint32_t getWordCount() const override;
+  GeoPosition getGeoPosition() const override;
+
+private:
+  rust::Box<IndexDataBox> data;
+};
+
+std::unique_ptr<Archive> archive_ctor_file(rust::Str path);
+std::unique_ptr<Archive> archive_ctor_path(rust::Slice<const uint8_t> path, int32_t preload_dirent_ranges);
+std::unique_ptr<Archive> archive_ctor_fd(int32_t fd);
+std::unique_ptr<Archive> archive_ctor_fd_range(int32_t fd, uint64_t offset, uint64_t size);
+std::unique_ptr<Archive> archive_ctor_fds(rust::Slice<const int32_t> fds, rust::Slice<const uint64_t> offsets, rust::Slice<const uint64_t> sizes);
+bool archive_check(const Archive &a);
+uint32_t archive_getAllEntryCount(const Archive &a);
+uint32_t archive_getArticleCount(const Archive &a);
+uint32_t archive_getEntryCount(const Archive &a);
+std::unique_ptr<std::string> archive_getChecksum(const Archive &a);
+std::unique_ptr<Entry> archive_getEntryByPath_idx(const Archive &a, uint32_t idx);
+std::unique_ptr<Entry> archive_getEntryByPath_str(const Archive &a, rust::Str path);
+std::unique_ptr<Entry> archive_getEntryByTitle_idx(const Archive &a, uint32_t idx);
+std::unique_ptr<Entry> archive_getEntryByTitle_str(const Archive &a, rust::Str title);
+std::unique_ptr<std::string> archive_getFilename(const Archive &a);
+uint64_t archive_getFilesize(const Archive &a);
+std::unique_ptr<Entry> archive_getMainEntry(const Archive &a);
+std::unique_ptr<Entry> archive_getRandomEntry(const Archive &a);
+std::unique_ptr<std::string> archive_getMetadata(const Archive &a, rust::Str name);
+std::unique_ptr<Item> archive_getMetadataItem(const Archive &a, rust::Str name);
+std::unique_ptr<std::vector<std::string>> archive_getMetadataKeys(const Archive &a);
+std::unique_ptr<std::vector<uint32_t>> archive_getIllustrationSizes(const Archive &a);
+bool archive_hasIllustration(const Archive &a, uint32_t size);
+std::unique_ptr<Item> archive_getIllustrationItem(const Archive &a, uint32_t size);
+std::unique_ptr<Uuid> archive_getUuid(const Archive &a);
+bool archive_hasChecksum(const Archive &a);
+bool archive_hasEntryByPath(const Archive &a, rust::Str path);
+bool archive_hasEntryByTitle(const Archive &a, rust::Str title);
+bool archive_hasFulltextIndex(const Archive &a);
+bool archive_hasMainEntry(const Archive &a);
+bool archive_hasNewNamespaceScheme(const Archive &a);
+bool archive_isMultiPart(const Archive &a);
+uint64_t zim_getClusterCacheMaxSize();
+uint64_t zim_getClusterCacheCurrentSize();
+void zim_setClusterCacheMaxSize(uint64_t size_in_bytes);
+uint64_t archive_getDirentCacheMaxSize(const Archive &a);
+uint64_t archive_getDirentCacheCurrentSize(const Archive &a);
+void archive_setDirentCacheMaxSize(const Archive &a, uint64_t nb_dirents);
+uint64_t archive_getDirentLookupCacheMaxSize(const Archive &a);
+std::unique_ptr<EntryRangeEfficient> archive_iterEfficient(const Archive &a);
+std::unique_ptr<EntryRangePath> archive_iterByPath(const Archive &a);
+std::unique_ptr<EntryRangeTitle> archive_iterByTitle(const Archive &a);
+std::unique_ptr<EntryRangePath> archive_findByPath(const Archive &a, rust::Str path);
+std::unique_ptr<EntryRangeTitle> archive_findByTitle(const Archive &a, rust::Str title);
+
+std::unique_ptr<IterEfficient> entryrangeefficient_begin(const EntryRangeEfficient &r);
+uint32_t entryrangeefficient_size(const EntryRangeEfficient &r);
+std::unique_ptr<EntryRangeEfficient> entryrangeefficient_offset(const EntryRangeEfficient &r, uint32_t start, uint32_t max);
+std::unique_ptr<IterEfficient> entryrangeefficient_begin_at(const EntryRangeEfficient &r, uint32_t start);
+std::unique_ptr<Entry> entryrangeefficient_entry_at(const EntryRangeEfficient &r, uint32_t pos);
+void iterefficient_inc(IterEfficient &it);
+std::unique_ptr<Entry> iterefficient_star(const IterEfficient &it);
+std::unique_ptr<IterPath> entryrangepath_begin(const EntryRangePath &r);
+uint32_t entryrangepath_size(const EntryRangePath &r);
+std::unique_ptr<EntryRangePath> entryrangepath_offset(const EntryRangePath &r, uint32_t start, uint32_t max);
+std::unique_ptr<IterPath> entryrangepath_begin_at(const EntryRangePath &r, uint32_t start);
+std::unique_ptr<Entry> entryrangepath_entry_at(const EntryRangePath &r, uint32_t pos);
+void iterpath_inc(IterPath &it);
+std::unique_ptr<Entry> iterpath_star(const IterPath &it);
+std::unique_ptr<IterTitle> entryrangetitle_begin(const EntryRangeTitle &r);
+uint32_t entryrangetitle_size(const EntryRangeTitle &r);
+std::unique_ptr<EntryRangeTitle> entryrangetitle_offset(const EntryRangeTitle &r, uint32_t start, uint32_t max);
+std::unique_ptr<IterTitle> entryrangetitle_begin_at(const EntryRangeTitle &r, uint32_t start);
+std::unique_ptr<Entry> entryrangetitle_entry_at(const EntryRangeTitle &r, uint32_t pos);
+void itertitle_inc(IterTitle &it);
+std::unique_ptr<Entry> itertitle_star(const IterTitle &it);
+
+const char *blob_data(const Blob &b);
+uint64_t blob_size(const Blob &b);
+
+uint32_t entry_getIndex(const Entry &e);
+std::unique_ptr<Item> entry_getItem(const Entry &e, bool follow);
+std::unique_ptr<std::string> entry_getPath(const Entry &e);
+std::unique_ptr<std::string> entry_getTitle(const Entry &e);
+std::unique_ptr<Item> entry_getRedirect(const Entry &e);
+std::unique_ptr<Entry> entry_getRedirectEntry(const Entry &e);
+bool entry_isRedirect(const Entry &e);
+
+std::unique_ptr<Blob> item_getData(const Item &i);
+std::unique_ptr<Blob> item_getData_offset(const Item &i, uint64_t offset, uint64_t size);
+uint32_t item_getIndex(const Item &i);
+std::unique_ptr<std::string> item_getMimetype(const Item &i);
+std::unique_ptr<std::string> item_getPath(const Item &i);
+std::unique_ptr<std::string> item_getTitle(const Item &i);
+uint64_t item_getSize(const Item &i);
+std::unique_ptr<std::string> item_getDirectAccessPath(const Item &i);
+uint64_t item_getDirectAccessOffset(const Item &i);
+
+std::unique_ptr<Query> query_ctor(rust::Str query);
+void query_setQuery(Query &q, rust::Str query);
+void query_setGeorange(Query &q, float latitude, float longitude, float distance);
+
+std::unique_ptr<Searcher> searcher_ctor(const Archive &a);
+void searcher_addArchive(Searcher &s, const Archive &a);
+std::unique_ptr<Search> searcher_search(Searcher &s, const Query &q);
+void searcher_setVerbose(Searcher &s, bool verbose);
+int32_t search_getEstimatedMatches(const Search &s);
+std::unique_ptr<SearchResultSet> search_getResults(const Search &s, int32_t start, int32_t max_results);
+std::unique_ptr<SearchIterator> searchresultset_begin(const SearchResultSet &s);
+std::unique_ptr<SearchIterator> searchresultset_end(const SearchResultSet &s);
+int32_t searchresultset_size(const SearchResultSet &s);
+bool searchiterator_operator_eq(const SearchIterator &a, const SearchIterator &b);
+void searchiterator_operator_inc(SearchIterator &it);
+void searchiterator_operator_dec(SearchIterator &it);
+std::unique_ptr<Entry> searchiterator_operator_star(const SearchIterator &it);
+int32_t searchiterator_getScore(const SearchIterator &it);
+std::unique_ptr<std::string> searchiterator_getSnippet(const SearchIterator &it);
+int32_t searchiterator_getWordCount(const SearchIterator &it);
+int32_t searchiterator_getSize(const SearchIterator &it);
+int32_t searchiterator_getFileIndex(const SearchIterator &it);
+std::unique_ptr<Uuid> searchiterator_getZimId(const SearchIterator &it);
+
+std::unique_ptr<SuggestionSearcher> suggestionsearcher_ctor(const Archive &a);
+void suggestionsearcher_setVerbose(SuggestionSearcher &s, bool verbose);
+std::unique_ptr<SuggestionSearch> suggestionsearcher_suggest(SuggestionSearcher &s, rust::Str query);
+int32_t suggestionsearch_getEstimatedMatches(const SuggestionSearch &s);
+std::unique_ptr<SuggestionResultSet> suggestionsearch_getResults(const SuggestionSearch &s, int32_t start, int32_t max_results);
+std::unique_ptr<SuggestionIterator> suggestionresultset_begin(const SuggestionResultSet &s);
+std::unique_ptr<SuggestionIterator> suggestionresultset_end(const SuggestionResultSet &s);
+int32_t suggestionresultset_size(const SuggestionResultSet &s);
+bool suggestioniterator_operator_eq(const SuggestionIterator &a, const SuggestionIterator &b);
+void suggestioniterator_operator_inc(SuggestionIterator &it);
+void suggestioniterator_operator_dec(SuggestionIterator &it);
+std::unique_ptr<SuggestionItem> suggestioniterator_operator_star(SuggestionIterator &it);
+std::unique_ptr<std::string> suggestionitem_getPath(const SuggestionItem &s);
+std::unique_ptr<std::string> suggestionitem_getTitle(const SuggestionItem &s);
+std::unique_ptr<std::string> suggestionitem_getSnippet(const SuggestionItem &s);
+bool suggestionitem_hasSnippet(const SuggestionItem &s);
+
+std::unique_ptr<Uuid> uuid_generate(rust::Str value);
+std::unique_ptr<std::string> uuid_std_string(const Uuid &u);
+
+std::unique_ptr<Creator> creator_ctor();
+void creator_configVerbose(Creator &c, bool verbose);
+void creator_configCompression(Creator &c, uint8_t compression);
+void creator_configClusterSize(Creator &c, uint64_t size);
+void creator_configIndexing(Creator &c, bool indexing, rust::Str language);
+void creator_configNbWorkers(Creator &c, uint32_t nb_workers);
+void creator_startZimCreation(Creator &c, rust::Slice<const uint8_t> path);
+void creator_addItem(Creator &c, rust::Box<WriterItemBox> item);
+void creator_addRedirection(Creator &c, rust::Str path, rust::Str title, rust::Str target, int64_t front_article);
+void creator_addAlias(Creator &c, rust::Str path, rust::Str title, rust::Str target);
+void creator_addMetadata(Creator &c, rust::Str name, rust::Slice<const uint8_t> content, rust::Str mimetype);
+void creator_addIllustration(Creator &c, uint32_t size, rust::Slice<const uint8_t> content);
+void creator_setMainPath(Creator &c, rust::Str path);
+void creator_finishZimCreation(Creator &c);
+
+} // namespace zim_rs
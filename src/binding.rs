@@ -0,0 +1,292 @@
+//! The cxx bridge to libzim, implemented in `binding.h` and `binding.cc`.
+//!
+//! Every call that can make libzim throw returns a `Result`: the exception is
+//! forwarded as a [`cxx::Exception`] whose message is tagged with the kind of the
+//! C++ exception, see `From<cxx::Exception> for Error`.
+
+use crate::writer::{
+    content_provider_feed, content_provider_size, index_data_content, index_data_geo_position,
+    index_data_has_index_data, index_data_is_default, index_data_keywords, index_data_title,
+    index_data_word_count, writer_item_content_provider, writer_item_hint, writer_item_index_data,
+    writer_item_mimetype, writer_item_path, writer_item_title, ContentProviderBox, IndexDataBox,
+    WriterItemBox,
+};
+
+#[cxx::bridge(namespace = "zim_rs")]
+#[allow(non_snake_case)]
+pub(crate) mod ffi {
+    /// `zim::writer::IndexData::GeoPosition`.
+    struct GeoPosition {
+        valid: bool,
+        latitude: f64,
+        longitude: f64,
+    }
+
+    extern "Rust" {
+        type WriterItemBox;
+        type ContentProviderBox;
+        type IndexDataBox;
+
+        fn writer_item_path(item: &WriterItemBox) -> Result<String>;
+        fn writer_item_title(item: &WriterItemBox) -> Result<String>;
+        fn writer_item_mimetype(item: &WriterItemBox) -> Result<String>;
+        /// Value of the `zim::writer::HintKeys` `key`, -1 if unset.
+        fn writer_item_hint(item: &WriterItemBox, key: u8) -> Result<i64>;
+        fn writer_item_content_provider(item: &WriterItemBox) -> Result<Box<ContentProviderBox>>;
+        fn writer_item_index_data(item: &WriterItemBox) -> Result<Box<IndexDataBox>>;
+
+        fn content_provider_size(provider: &ContentProviderBox) -> Result<u64>;
+        /// The next chunk of content, empty once everything was fed.
+        fn content_provider_feed(provider: &mut ContentProviderBox) -> Result<Vec<u8>>;
+
+        /// Whether libzim should extract the index data from the content itself.
+        fn index_data_is_default(data: &IndexDataBox) -> bool;
+        fn index_data_has_index_data(data: &IndexDataBox) -> Result<bool>;
+        fn index_data_title(data: &IndexDataBox) -> Result<String>;
+        fn index_data_content(data: &IndexDataBox) -> Result<String>;
+        fn index_data_keywords(data: &IndexDataBox) -> Result<String>;
+        fn index_data_word_count(data: &IndexDataBox) -> Result<u32>;
+        fn index_data_geo_position(data: &IndexDataBox) -> Result<GeoPosition>;
+    }
+
+    #[namespace = "zim"]
+    unsafe extern "C++" {
+        include!("zim-rs/src/binding.h");
+
+        type Archive;
+        type Entry;
+        type Item;
+        type Blob;
+        type Uuid;
+        type Query;
+        type Searcher;
+        type Search;
+        type SearchResultSet;
+        type SearchIterator;
+        type SuggestionSearcher;
+        type SuggestionSearch;
+        type SuggestionResultSet;
+        type SuggestionIterator;
+        type SuggestionItem;
+    }
+
+    #[namespace = "zim::writer"]
+    unsafe extern "C++" {
+        type Creator;
+    }
+
+    unsafe extern "C++" {
+        include!("zim-rs/src/binding.h");
+
+        /// `zim::Archive::EntryRange` and `zim::Archive::iterator` of each entry order.
+        type EntryRangeEfficient;
+        type IterEfficient;
+        type EntryRangePath;
+        type IterPath;
+        type EntryRangeTitle;
+        type IterTitle;
+
+        fn archive_ctor_file(path: &str) -> Result<UniquePtr<Archive>>;
+        /// `preload_dirent_ranges` is passed to `zim::OpenConfig::preloadDirentRanges`,
+        /// unless negative.
+        fn archive_ctor_path(path: &[u8], preload_dirent_ranges: i32)
+            -> Result<UniquePtr<Archive>>;
+        fn archive_ctor_fd(fd: i32) -> Result<UniquePtr<Archive>>;
+        fn archive_ctor_fd_range(fd: i32, offset: u64, size: u64) -> Result<UniquePtr<Archive>>;
+        fn archive_ctor_fds(
+            fds: &[i32],
+            offsets: &[u64],
+            sizes: &[u64],
+        ) -> Result<UniquePtr<Archive>>;
+        fn archive_check(a: &Archive) -> Result<bool>;
+        fn archive_getAllEntryCount(a: &Archive) -> u32;
+        fn archive_getArticleCount(a: &Archive) -> u32;
+        fn archive_getEntryCount(a: &Archive) -> u32;
+        fn archive_getChecksum(a: &Archive) -> Result<UniquePtr<CxxString>>;
+        fn archive_getEntryByPath_idx(a: &Archive, idx: u32) -> Result<UniquePtr<Entry>>;
+        fn archive_getEntryByPath_str(a: &Archive, path: &str) -> Result<UniquePtr<Entry>>;
+        fn archive_getEntryByTitle_idx(a: &Archive, idx: u32) -> Result<UniquePtr<Entry>>;
+        fn archive_getEntryByTitle_str(a: &Archive, title: &str) -> Result<UniquePtr<Entry>>;
+        fn archive_getFilename(a: &Archive) -> UniquePtr<CxxString>;
+        fn archive_getFilesize(a: &Archive) -> u64;
+        fn archive_getMainEntry(a: &Archive) -> Result<UniquePtr<Entry>>;
+        fn archive_getRandomEntry(a: &Archive) -> Result<UniquePtr<Entry>>;
+        fn archive_getMetadata(a: &Archive, name: &str) -> Result<UniquePtr<CxxString>>;
+        fn archive_getMetadataItem(a: &Archive, name: &str) -> Result<UniquePtr<Item>>;
+        fn archive_getMetadataKeys(a: &Archive) -> Result<UniquePtr<CxxVector<CxxString>>>;
+        fn archive_getIllustrationSizes(a: &Archive) -> Result<UniquePtr<CxxVector<u32>>>;
+        fn archive_hasIllustration(a: &Archive, size: u32) -> Result<bool>;
+        fn archive_getIllustrationItem(a: &Archive, size: u32) -> Result<UniquePtr<Item>>;
+        fn archive_getUuid(a: &Archive) -> UniquePtr<Uuid>;
+        fn archive_hasChecksum(a: &Archive) -> bool;
+        fn archive_hasEntryByPath(a: &Archive, path: &str) -> Result<bool>;
+        fn archive_hasEntryByTitle(a: &Archive, title: &str) -> Result<bool>;
+        fn archive_hasFulltextIndex(a: &Archive) -> Result<bool>;
+        fn archive_hasMainEntry(a: &Archive) -> bool;
+        fn archive_hasNewNamespaceScheme(a: &Archive) -> bool;
+        fn archive_isMultiPart(a: &Archive) -> bool;
+        fn zim_getClusterCacheMaxSize() -> u64;
+        fn zim_getClusterCacheCurrentSize() -> u64;
+        fn zim_setClusterCacheMaxSize(size_in_bytes: u64);
+        fn archive_getDirentCacheMaxSize(a: &Archive) -> u64;
+        fn archive_getDirentCacheCurrentSize(a: &Archive) -> u64;
+        fn archive_setDirentCacheMaxSize(a: &Archive, nb_dirents: u64);
+        fn archive_getDirentLookupCacheMaxSize(a: &Archive) -> u64;
+        fn archive_iterEfficient(a: &Archive) -> UniquePtr<EntryRangeEfficient>;
+        fn archive_iterByPath(a: &Archive) -> UniquePtr<EntryRangePath>;
+        fn archive_iterByTitle(a: &Archive) -> UniquePtr<EntryRangeTitle>;
+        fn archive_findByPath(a: &Archive, path: &str) -> Result<UniquePtr<EntryRangePath>>;
+        fn archive_findByTitle(a: &Archive, title: &str) -> Result<UniquePtr<EntryRangeTitle>>;
+
+        fn entryrangeefficient_begin(r: &EntryRangeEfficient) -> UniquePtr<IterEfficient>;
+        fn entryrangeefficient_size(r: &EntryRangeEfficient) -> u32;
+        fn entryrangeefficient_offset(
+            r: &EntryRangeEfficient,
+            start: u32,
+            max: u32,
+        ) -> UniquePtr<EntryRangeEfficient>;
+        fn entryrangeefficient_begin_at(
+            r: &EntryRangeEfficient,
+            start: u32,
+        ) -> UniquePtr<IterEfficient>;
+        fn entryrangeefficient_entry_at(
+            r: &EntryRangeEfficient,
+            pos: u32,
+        ) -> Result<UniquePtr<Entry>>;
+        fn iterefficient_inc(it: Pin<&mut IterEfficient>);
+        fn iterefficient_star(it: &IterEfficient) -> Result<UniquePtr<Entry>>;
+        fn entryrangepath_begin(r: &EntryRangePath) -> UniquePtr<IterPath>;
+        fn entryrangepath_size(r: &EntryRangePath) -> u32;
+        fn entryrangepath_offset(
+            r: &EntryRangePath,
+            start: u32,
+            max: u32,
+        ) -> UniquePtr<EntryRangePath>;
+        fn entryrangepath_begin_at(r: &EntryRangePath, start: u32) -> UniquePtr<IterPath>;
+        fn entryrangepath_entry_at(r: &EntryRangePath, pos: u32) -> Result<UniquePtr<Entry>>;
+        fn iterpath_inc(it: Pin<&mut IterPath>);
+        fn iterpath_star(it: &IterPath) -> Result<UniquePtr<Entry>>;
+        fn entryrangetitle_begin(r: &EntryRangeTitle) -> UniquePtr<IterTitle>;
+        fn entryrangetitle_size(r: &EntryRangeTitle) -> u32;
+        fn entryrangetitle_offset(
+            r: &EntryRangeTitle,
+            start: u32,
+            max: u32,
+        ) -> UniquePtr<EntryRangeTitle>;
+        fn entryrangetitle_begin_at(r: &EntryRangeTitle, start: u32) -> UniquePtr<IterTitle>;
+        fn entryrangetitle_entry_at(r: &EntryRangeTitle, pos: u32) -> Result<UniquePtr<Entry>>;
+        fn itertitle_inc(it: Pin<&mut IterTitle>);
+        fn itertitle_star(it: &IterTitle) -> Result<UniquePtr<Entry>>;
+
+        fn blob_data(b: &Blob) -> *const c_char;
+        fn blob_size(b: &Blob) -> u64;
+
+        fn entry_getIndex(e: &Entry) -> u32;
+        fn entry_getItem(e: &Entry, follow: bool) -> Result<UniquePtr<Item>>;
+        fn entry_getPath(e: &Entry) -> UniquePtr<CxxString>;
+        fn entry_getTitle(e: &Entry) -> UniquePtr<CxxString>;
+        fn entry_getRedirect(e: &Entry) -> Result<UniquePtr<Item>>;
+        fn entry_getRedirectEntry(e: &Entry) -> Result<UniquePtr<Entry>>;
+        fn entry_isRedirect(e: &Entry) -> bool;
+
+        fn item_getData(i: &Item) -> Result<UniquePtr<Blob>>;
+        fn item_getData_offset(i: &Item, offset: u64, size: u64) -> Result<UniquePtr<Blob>>;
+        fn item_getIndex(i: &Item) -> u32;
+        fn item_getMimetype(i: &Item) -> Result<UniquePtr<CxxString>>;
+        fn item_getPath(i: &Item) -> UniquePtr<CxxString>;
+        fn item_getTitle(i: &Item) -> UniquePtr<CxxString>;
+        fn item_getSize(i: &Item) -> u64;
+        /// Empty if the item cannot be read directly from a file.
+        fn item_getDirectAccessPath(i: &Item) -> Result<UniquePtr<CxxString>>;
+        fn item_getDirectAccessOffset(i: &Item) -> Result<u64>;
+
+        fn query_ctor(query: &str) -> UniquePtr<Query>;
+        fn query_setQuery(q: Pin<&mut Query>, query: &str);
+        fn query_setGeorange(q: Pin<&mut Query>, latitude: f32, longitude: f32, distance: f32);
+
+        fn searcher_ctor(a: &Archive) -> Result<UniquePtr<Searcher>>;
+        fn searcher_addArchive(s: Pin<&mut Searcher>, a: &Archive);
+        fn searcher_search(s: Pin<&mut Searcher>, q: &Query) -> Result<UniquePtr<Search>>;
+        fn searcher_setVerbose(s: Pin<&mut Searcher>, verbose: bool);
+        fn search_getEstimatedMatches(s: &Search) -> Result<i32>;
+        fn search_getResults(
+            s: &Search,
+            start: i32,
+            max_results: i32,
+        ) -> Result<UniquePtr<SearchResultSet>>;
+        fn searchresultset_begin(s: &SearchResultSet) -> UniquePtr<SearchIterator>;
+        fn searchresultset_end(s: &SearchResultSet) -> UniquePtr<SearchIterator>;
+        fn searchresultset_size(s: &SearchResultSet) -> i32;
+        fn searchiterator_operator_eq(a: &SearchIterator, b: &SearchIterator) -> bool;
+        fn searchiterator_operator_inc(it: Pin<&mut SearchIterator>);
+        fn searchiterator_operator_dec(it: Pin<&mut SearchIterator>);
+        fn searchiterator_operator_star(it: &SearchIterator) -> Result<UniquePtr<Entry>>;
+        fn searchiterator_getScore(it: &SearchIterator) -> i32;
+        fn searchiterator_getSnippet(it: &SearchIterator) -> Result<UniquePtr<CxxString>>;
+        fn searchiterator_getWordCount(it: &SearchIterator) -> Result<i32>;
+        fn searchiterator_getSize(it: &SearchIterator) -> Result<i32>;
+        fn searchiterator_getFileIndex(it: &SearchIterator) -> i32;
+        fn searchiterator_getZimId(it: &SearchIterator) -> Result<UniquePtr<Uuid>>;
+
+        fn suggestionsearcher_ctor(a: &Archive) -> Result<UniquePtr<SuggestionSearcher>>;
+        fn suggestionsearcher_setVerbose(s: Pin<&mut SuggestionSearcher>, verbose: bool);
+        fn suggestionsearcher_suggest(
+            s: Pin<&mut SuggestionSearcher>,
+            query: &str,
+        ) -> Result<UniquePtr<SuggestionSearch>>;
+        fn suggestionsearch_getEstimatedMatches(s: &SuggestionSearch) -> Result<i32>;
+        fn suggestionsearch_getResults(
+            s: &SuggestionSearch,
+            start: i32,
+            max_results: i32,
+        ) -> Result<UniquePtr<SuggestionResultSet>>;
+        fn suggestionresultset_begin(s: &SuggestionResultSet) -> UniquePtr<SuggestionIterator>;
+        fn suggestionresultset_end(s: &SuggestionResultSet) -> UniquePtr<SuggestionIterator>;
+        fn suggestionresultset_size(s: &SuggestionResultSet) -> i32;
+        fn suggestioniterator_operator_eq(a: &SuggestionIterator, b: &SuggestionIterator) -> bool;
+        fn suggestioniterator_operator_inc(it: Pin<&mut SuggestionIterator>);
+        fn suggestioniterator_operator_dec(it: Pin<&mut SuggestionIterator>);
+        fn suggestioniterator_operator_star(
+            it: Pin<&mut SuggestionIterator>,
+        ) -> Result<UniquePtr<SuggestionItem>>;
+        fn suggestionitem_getPath(s: &SuggestionItem) -> UniquePtr<CxxString>;
+        fn suggestionitem_getTitle(s: &SuggestionItem) -> UniquePtr<CxxString>;
+        fn suggestionitem_getSnippet(s: &SuggestionItem) -> UniquePtr<CxxString>;
+        fn suggestionitem_hasSnippet(s: &SuggestionItem) -> bool;
+
+        fn uuid_generate(value: &str) -> UniquePtr<Uuid>;
+        fn uuid_std_string(u: &Uuid) -> UniquePtr<CxxString>;
+
+        fn creator_ctor() -> UniquePtr<Creator>;
+        fn creator_configVerbose(c: Pin<&mut Creator>, verbose: bool);
+        fn creator_configCompression(c: Pin<&mut Creator>, compression: u8);
+        fn creator_configClusterSize(c: Pin<&mut Creator>, size: u64);
+        fn creator_configIndexing(c: Pin<&mut Creator>, indexing: bool, language: &str);
+        fn creator_configNbWorkers(c: Pin<&mut Creator>, nb_workers: u32);
+        fn creator_startZimCreation(c: Pin<&mut Creator>, path: &[u8]) -> Result<()>;
+        fn creator_addItem(c: Pin<&mut Creator>, item: Box<WriterItemBox>) -> Result<()>;
+        /// `front_article` is the `FRONT_ARTICLE` hint, -1 if unset.
+        fn creator_addRedirection(
+            c: Pin<&mut Creator>,
+            path: &str,
+            title: &str,
+            target: &str,
+            front_article: i64,
+        ) -> Result<()>;
+        fn creator_addAlias(
+            c: Pin<&mut Creator>,
+            path: &str,
+            title: &str,
+            target: &str,
+        ) -> Result<()>;
+        fn creator_addMetadata(
+            c: Pin<&mut Creator>,
+            name: &str,
+            content: &[u8],
+            mimetype: &str,
+        ) -> Result<()>;
+        fn creator_addIllustration(c: Pin<&mut Creator>, size: u32, content: &[u8]) -> Result<()>;
+        fn creator_setMainPath(c: Pin<&mut Creator>, path: &str);
+        fn creator_finishZimCreation(c: Pin<&mut Creator>) -> Result<()>;
+    }
+}
@@ -1,32 +1,59 @@
+use std::{ops::RangeBounds, sync::Arc};
+
+#[cfg(not(feature = "pure-rust"))]
 use cxx::UniquePtr;
-use zim_sys::binding::ffi;
 
+#[cfg(not(feature = "pure-rust"))]
+use crate::binding::ffi;
+use crate::{archive::resolve_range, error::Error};
+
+/// Bytes read from an archive.
+///
+/// Cloning and [`Blob::slice`] are cheap: they share the same allocation.
+#[derive(Clone)]
 pub struct Blob {
+    inner: Arc<BlobInner>,
+    start: usize,
+    end: usize,
+}
+
+#[cfg(not(feature = "pure-rust"))]
+struct BlobInner {
     ptr: UniquePtr<ffi::Blob>,
 }
 
+#[cfg(feature = "pure-rust")]
+struct BlobInner {
+    data: Arc<[u8]>,
+}
+
 // [SAFETY]
 // Blob is readonly and the data is valid as long as we keep a reference to ffi::Blob
-// (which is validated by `UniquePtr` and rust borrow rules).
+// (which is validated by `UniquePtr`, the `Arc` shared by all slices and rust borrow rules).
 // libzim itself is threadsafe, so it is valid to call libzim method from different threads.
-unsafe impl Sync for Blob {}
-unsafe impl Send for Blob {}
+#[cfg(not(feature = "pure-rust"))]
+unsafe impl Sync for BlobInner {}
+#[cfg(not(feature = "pure-rust"))]
+unsafe impl Send for BlobInner {}
 
+#[cfg(not(feature = "pure-rust"))]
 impl Blob {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Blob>) -> Result<Blob, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Blob { ptr }),
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Blob>) -> Blob {
+        let end = ffi::blob_size(ptr.as_ref().unwrap()) as usize;
+        Blob {
+            inner: Arc::new(BlobInner { ptr }),
+            start: 0,
+            end,
         }
     }
+}
 
-    fn inner_ref(&self) -> &ffi::Blob {
-        self.ptr.as_ref().unwrap()
-    }
-
-    pub fn data(&self) -> &[u8] {
-        let data_ptr = ffi::blob_data(self.inner_ref()) as *const u8;
-        let data_size = ffi::blob_size(self.inner_ref()) as usize;
+#[cfg(not(feature = "pure-rust"))]
+impl BlobInner {
+    fn data(&self) -> &[u8] {
+        let ptr = self.ptr.as_ref().unwrap();
+        let data_ptr = ffi::blob_data(ptr) as *const u8;
+        let data_size = ffi::blob_size(ptr) as usize;
         let data_ptr = if data_ptr.is_null() {
             // libzim may return an empty blob if something goes wrong (invalid request, size too big, ...)
             // In this case, size is also 0.
@@ -46,9 +73,57 @@ impl Blob {
         //   guaranteed to exist as long as the blob exists.
         unsafe { std::slice::from_raw_parts(data_ptr, data_size) }
     }
+}
+
+#[cfg(feature = "pure-rust")]
+impl Blob {
+    /// The bytes at `range` of `data`, which is usually a whole decompressed cluster.
+    pub(crate) fn from_shared(data: Arc<[u8]>, range: std::ops::Range<usize>) -> Blob {
+        Blob {
+            inner: Arc::new(BlobInner { data }),
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+#[cfg(feature = "pure-rust")]
+impl BlobInner {
+    fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Blob {
+    pub fn data(&self) -> &[u8] {
+        &self.inner.data()[self.start..self.end]
+    }
 
     pub fn size(&self) -> u64 {
-        ffi::blob_size(self.inner_ref())
+        (self.end - self.start) as u64
+    }
+
+    /// The bytes at `range` of this blob, sharing its allocation.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> Result<Blob, Error> {
+        let (start, end) = resolve_range(range, self.end - self.start)?;
+        Ok(Blob {
+            inner: self.inner.clone(),
+            start: self.start + start,
+            end: self.start + end,
+        })
+    }
+
+    /// Hand the blob over to a [`bytes::Bytes`] without copying the data.
+    #[cfg(feature = "bytes")]
+    pub fn into_bytes(self) -> bytes::Bytes {
+        bytes::Bytes::from_owner(self)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<Blob> for bytes::Bytes {
+    fn from(blob: Blob) -> Self {
+        blob.into_bytes()
     }
 }
 
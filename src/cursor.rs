@@ -0,0 +1,292 @@
+use std::{fmt, str::FromStr};
+
+use crate::{archive::Archive, error::Error, search::Query, uuid::Uuid};
+
+/// Layout version written at the start of every encoded cursor, cursors of any other
+/// version are rejected.
+const CURSOR_VERSION: u8 = 3;
+
+/// Longest path prefix or mimetype restriction a cursor can record, in bytes.
+pub(crate) const MAX_RESTRICTION_LEN: usize = u16::MAX as usize;
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Everything needed to rebuild a search or suggestion and continue it at `offset`,
+/// without keeping any state between requests: the query with its georange,
+/// restrictions and result limit, and the archives searched.
+///
+/// A cursor is serialized to an opaque URL-safe string with [`SearchCursor::encode`]
+/// (or `to_string`) and parsed back with [`SearchCursor::decode`] (or `parse`).
+/// Archives are recorded by uuid, so a cursor can be resumed by any
+/// [`Searcher`](crate::search::Searcher) or
+/// [`SuggestionSearcher`](crate::suggestion::SuggestionSearcher) opened on the same archives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchCursor {
+    query: String,
+    georange: Option<(f32, f32, f32)>,
+    path_prefix: Option<String>,
+    mimetype: Option<String>,
+    max_results: Option<usize>,
+    archives: Vec<Uuid>,
+    offset: usize,
+}
+
+impl SearchCursor {
+    pub fn new(
+        query: &str,
+        georange: Option<(f32, f32, f32)>,
+        archives: Vec<Uuid>,
+        offset: usize,
+    ) -> SearchCursor {
+        SearchCursor {
+            query: query.to_string(),
+            georange,
+            path_prefix: None,
+            mimetype: None,
+            max_results: None,
+            archives,
+            offset,
+        }
+    }
+
+    /// A cursor for `query` that does not know its archives yet, see
+    /// [`SearchCursor::with_archives`].
+    pub(crate) fn for_query(query: &Query) -> SearchCursor {
+        SearchCursor {
+            path_prefix: query.path_prefix().map(str::to_string),
+            mimetype: query.mimetype().map(str::to_string),
+            max_results: query.max_results(),
+            ..SearchCursor::new(query.text(), query.georange(), Vec::new(), 0)
+        }
+    }
+
+    /// The same search on `archives`, continued at `offset`.
+    pub(crate) fn with_archives(
+        &self,
+        archives: &[Archive],
+        offset: usize,
+    ) -> Result<SearchCursor, Error> {
+        let archives = archives
+            .iter()
+            .map(Archive::get_uuid)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SearchCursor {
+            archives,
+            offset,
+            ..self.clone()
+        })
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// `(latitude, longitude, distance in meters)` the search is restricted to.
+    pub fn georange(&self) -> Option<(f32, f32, f32)> {
+        self.georange
+    }
+
+    pub fn path_prefix(&self) -> Option<&str> {
+        self.path_prefix.as_deref()
+    }
+
+    pub fn mimetype(&self) -> Option<&str> {
+        self.mimetype.as_deref()
+    }
+
+    /// Limit of the total number of results.
+    pub fn max_results(&self) -> Option<usize> {
+        self.max_results
+    }
+
+    /// Uuids of the archives searched, in the order they were added.
+    pub fn archives(&self) -> &[Uuid] {
+        &self.archives
+    }
+
+    /// Number of results already consumed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The same search, continued at `offset`.
+    pub fn with_offset(&self, offset: usize) -> SearchCursor {
+        SearchCursor {
+            offset,
+            ..self.clone()
+        }
+    }
+
+    /// Check that `archives` are the ones this cursor was created for.
+    pub(crate) fn check_archives(&self, archives: &[Archive]) -> Result<(), Error> {
+        let mut expected = self.archives.clone();
+        let mut actual = archives
+            .iter()
+            .map(Archive::get_uuid)
+            .collect::<Result<Vec<_>, _>>()?;
+        expected.sort();
+        actual.sort();
+        match expected == actual {
+            true => Ok(()),
+            false => Err(Error::InvalidArgument(
+                "cursor was created for a different set of archives".to_string(),
+            )),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bytes = vec![CURSOR_VERSION];
+        bytes.extend_from_slice(&(self.offset as u64).to_le_bytes());
+        match self.georange {
+            Some((latitude, longitude, distance)) => {
+                bytes.push(1);
+                for value in [latitude, longitude, distance] {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            None => bytes.push(0),
+        }
+        for value in [&self.path_prefix, &self.mimetype] {
+            match value {
+                Some(value) => {
+                    // Longer restrictions are rejected by QueryBuilder::build.
+                    bytes.push(1);
+                    bytes.extend_from_slice(&(value.len() as u16).to_le_bytes());
+                    bytes.extend_from_slice(value.as_bytes());
+                }
+                None => bytes.push(0),
+            }
+        }
+        match self.max_results {
+            Some(max_results) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&(max_results as u64).to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes.extend_from_slice(&(self.archives.len() as u32).to_le_bytes());
+        for uuid in &self.archives {
+            bytes.extend_from_slice(&uuid.as_bytes());
+        }
+        bytes.extend_from_slice(self.query.as_bytes());
+        base64url_encode(&bytes)
+    }
+
+    pub fn decode(encoded: &str) -> Result<SearchCursor, Error> {
+        let invalid = || Error::InvalidArgument("malformed search cursor".to_string());
+        let bytes = base64url_decode(encoded).ok_or_else(invalid)?;
+        let mut reader = CursorReader { bytes: &bytes };
+        if reader.take::<1>().ok_or_else(invalid)? != [CURSOR_VERSION] {
+            return Err(Error::InvalidArgument(
+                "unsupported search cursor version".to_string(),
+            ));
+        }
+        let offset = u64::from_le_bytes(reader.take().ok_or_else(invalid)?);
+        let offset = usize::try_from(offset).map_err(|_| invalid())?;
+        let georange = match reader.take::<1>().ok_or_else(invalid)? {
+            [0] => None,
+            [1] => {
+                let mut value = || reader.take().map(f32::from_le_bytes).ok_or_else(invalid);
+                Some((value()?, value()?, value()?))
+            }
+            _ => return Err(invalid()),
+        };
+        let mut string = || match reader.take::<1>().ok_or_else(invalid)? {
+            [0] => Ok(None),
+            [1] => {
+                let len = u16::from_le_bytes(reader.take().ok_or_else(invalid)?);
+                let value = reader.take_slice(len as usize).ok_or_else(invalid)?;
+                let value = std::str::from_utf8(value).map_err(|_| invalid())?;
+                Ok(Some(value.to_string()))
+            }
+            _ => Err(invalid()),
+        };
+        let path_prefix = string()?;
+        let mimetype = string()?;
+        let max_results = match reader.take::<1>().ok_or_else(invalid)? {
+            [0] => None,
+            [1] => {
+                let max_results = u64::from_le_bytes(reader.take().ok_or_else(invalid)?);
+                Some(usize::try_from(max_results).map_err(|_| invalid())?)
+            }
+            _ => return Err(invalid()),
+        };
+        let count = u32::from_le_bytes(reader.take().ok_or_else(invalid)?);
+        let archives = (0..count)
+            .map(|_| reader.take().map(Uuid::from_bytes).ok_or_else(invalid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let query = std::str::from_utf8(reader.bytes).map_err(|_| invalid())?;
+        Ok(SearchCursor {
+            path_prefix,
+            mimetype,
+            max_results,
+            ..SearchCursor::new(query, georange, archives, offset)
+        })
+    }
+}
+
+impl fmt::Display for SearchCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl FromStr for SearchCursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SearchCursor::decode(s)
+    }
+}
+
+struct CursorReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl CursorReader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take_slice(N)?.try_into().ok()
+    }
+
+    fn take_slice(&mut self, len: usize) -> Option<&[u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Some(head)
+    }
+}
+
+/// Unpadded base64url (RFC 4648 §5).
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64URL[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn base64url_decode(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64URL.iter().position(|&b| b == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
@@ -1,20 +1,27 @@
 use cxx::{CxxString, CxxVector, UniquePtr};
 
-pub(crate) fn string_from_ptr(ptr: UniquePtr<CxxString>) -> Result<String, ()> {
-    match ptr.is_null() {
-        true => Err(()),
-        false => Ok(ptr.as_ref().unwrap().to_string()),
-    }
+use crate::error::Error;
+
+pub(crate) fn string_from_ptr(ptr: UniquePtr<CxxString>) -> String {
+    ptr.as_ref().unwrap().to_string()
+}
+
+pub(crate) fn vec_string_from_ptr(ptr: UniquePtr<CxxVector<CxxString>>) -> Vec<String> {
+    ptr.as_ref()
+        .unwrap()
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()
+}
+
+/// Convert a size or offset to the `int` libzim expects, rejecting values it cannot hold.
+pub(crate) fn int_from_usize(value: usize, name: &str) -> Result<i32, Error> {
+    i32::try_from(value).map_err(|_| {
+        Error::InvalidArgument(format!("{} {} is larger than {}", name, value, i32::MAX))
+    })
 }
 
-pub(crate) fn vec_string_from_ptr(ptr: UniquePtr<CxxVector<CxxString>>) -> Result<Vec<String>, ()> {
-    match ptr.is_null() {
-        true => Err(()),
-        false => Ok(ptr
-            .as_ref()
-            .unwrap()
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>()),
-    }
+/// Convert a count returned by libzim as `int`, where negative values mean none.
+pub(crate) fn usize_from_int(value: i32) -> usize {
+    usize::try_from(value).unwrap_or(0)
 }
@@ -1,17 +1,71 @@
+use std::collections::HashSet;
+#[cfg(feature = "pure-rust")]
+use std::sync::Arc;
+
+#[cfg(not(feature = "pure-rust"))]
 use cxx::UniquePtr;
-use zim_sys::binding::ffi;
 
-use crate::{cxx::string_from_ptr, item::Item};
+use crate::{archive::Archive, error::Error};
+#[cfg(not(feature = "pure-rust"))]
+use crate::{binding::ffi, cxx::string_from_ptr, item::Item};
+
+/// The outcome of [`Entry::resolve`].
+pub struct RedirectChain {
+    /// The first entry of the chain that is not a redirect.
+    pub target: Entry,
+    /// The redirects followed to reach `target`, in order, starting with the
+    /// resolved entry itself. Empty if it was not a redirect.
+    pub hops: Vec<Entry>,
+}
 
+#[cfg(not(feature = "pure-rust"))]
 pub struct Entry {
     ptr: UniquePtr<ffi::Entry>,
+    archive: Archive,
+}
+
+#[cfg(feature = "pure-rust")]
+pub struct Entry {
+    index: u32,
+    dirent: Arc<crate::native::format::Dirent>,
+    archive: Archive,
+}
+
+// [SAFETY]
+// Entry is readonly and keeps its parent archive alive through `archive`.
+// libzim itself is threadsafe, so it is valid to call libzim method from different threads.
+#[cfg(not(feature = "pure-rust"))]
+unsafe impl Send for Entry {}
+
+#[cfg(feature = "pure-rust")]
+impl Entry {
+    pub(crate) fn from_dirent(
+        index: u32,
+        dirent: Arc<crate::native::format::Dirent>,
+        archive: &Archive,
+    ) -> Entry {
+        Entry {
+            index,
+            dirent,
+            archive: archive.clone(),
+        }
+    }
+
+    pub(crate) fn dirent(&self) -> &Arc<crate::native::format::Dirent> {
+        &self.dirent
+    }
+
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
 }
 
+#[cfg(not(feature = "pure-rust"))]
 impl Entry {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Entry>) -> Result<Entry, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Entry { ptr }),
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Entry>, archive: &Archive) -> Entry {
+        Entry {
+            ptr,
+            archive: archive.clone(),
         }
     }
 
@@ -25,27 +79,82 @@ impl Entry {
 
     pub fn get_title(&self) -> String {
         string_from_ptr(ffi::entry_getTitle(self.inner_ref()))
-            .expect("Entry::get_title should be infallible")
     }
 
     pub fn get_path(&self) -> String {
         string_from_ptr(ffi::entry_getPath(self.inner_ref()))
-            .expect("Entry::get_path should be infallible")
     }
 
-    pub fn get_item(&self, follow: bool) -> Result<Item, ()> {
-        Item::from_ptr(ffi::entry_getItem(self.inner_ref(), follow))
+    pub fn get_item(&self, follow: bool) -> Result<Item, Error> {
+        let item = ffi::entry_getItem(self.inner_ref(), follow)?;
+        Ok(Item::from_ptr(item, &self.archive))
     }
 
-    pub fn get_redirect(&self) -> Result<Item, ()> {
-        Item::from_ptr(ffi::entry_getRedirect(self.inner_ref()))
+    pub fn get_redirect(&self) -> Result<Item, Error> {
+        let item = ffi::entry_getRedirect(self.inner_ref())?;
+        Ok(Item::from_ptr(item, &self.archive))
     }
 
-    pub fn get_redirect_entry(&self) -> Result<Entry, ()> {
-        Entry::from_ptr(ffi::entry_getRedirectEntry(self.inner_ref()))
+    pub fn get_redirect_entry(&self) -> Result<Entry, Error> {
+        let entry = ffi::entry_getRedirectEntry(self.inner_ref())?;
+        Ok(Entry::from_ptr(entry, &self.archive))
     }
 
     pub fn get_index(&self) -> u32 {
         ffi::entry_getIndex(self.inner_ref())
     }
 }
+
+impl Entry {
+    /// Follow redirects until a non-redirect entry, keeping every hop.
+    ///
+    /// Fails with [`Error::RedirectLoop`] if an entry shows up twice, with
+    /// [`Error::DanglingRedirect`] if a redirect target does not exist and with
+    /// [`Error::TooManyRedirects`] after `max_depth` hops.
+    pub fn resolve(&self, max_depth: usize) -> Result<RedirectChain, Error> {
+        let mut hops: Vec<Entry> = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current = self.archive.get_entry_bypath_index(self.get_index())?;
+        while current.is_redirect() {
+            let chain = || {
+                hops.iter()
+                    .chain(std::iter::once(&current))
+                    .map(Entry::get_path)
+                    .collect::<Vec<_>>()
+                    .join(" -> ")
+            };
+            if !visited.insert(current.get_index()) {
+                return Err(Error::RedirectLoop(chain()));
+            }
+            if hops.len() == max_depth {
+                return Err(Error::TooManyRedirects(format!(
+                    "more than {} redirects: {}",
+                    max_depth,
+                    chain()
+                )));
+            }
+            let next = current.get_redirect_entry().map_err(|err| match err {
+                Error::EntryNotFound(_) | Error::InvalidArgument(_) => {
+                    Error::DanglingRedirect(format!("{} -> missing entry", chain()))
+                }
+                err => err,
+            })?;
+            hops.push(current);
+            current = next;
+        }
+        Ok(RedirectChain {
+            target: current,
+            hops,
+        })
+    }
+
+    #[cfg(feature = "pure-rust")]
+    pub(crate) fn not_a_redirect(&self) -> Error {
+        Error::InvalidArgument(format!("entry {} is not a redirect", self.get_path()))
+    }
+
+    /// The archive this entry was read from.
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+}
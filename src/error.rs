@@ -0,0 +1,111 @@
+use std::fmt;
+
+/// Separator between the kind and the message of the exceptions forwarded by
+/// `binding.h`.
+#[cfg(not(feature = "pure-rust"))]
+const KIND_SEPARATOR: char = '\u{1f}';
+
+/// Errors returned by the safe wrappers around libzim.
+///
+/// Each variant carries the message reported by libzim (or by the wrapper when
+/// libzim gives no detail) so callers can both match on the kind of failure and
+/// show something useful to the user.
+///
+/// The two backends do not report a file that is not a ZIM archive the same way:
+/// libzim throws the same exception for a bad magic number or a truncated header
+/// as for any other format error, so it is an [`Error::ZimFileFormat`], while the
+/// `pure-rust` reader reports it as an [`Error::InvalidFormat`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// No entry, item or metadata matches the requested path, title or index.
+    EntryNotFound(String),
+    /// The file is not a ZIM archive (bad magic number or truncated header).
+    ///
+    /// Only returned by the `pure-rust` reader, libzim reports these as
+    /// [`Error::ZimFileFormat`].
+    InvalidFormat(String),
+    /// The file is a ZIM archive but cannot be read (corrupt dirents, clusters,
+    /// pointer lists, ...), or its major version is not supported.
+    ///
+    /// With libzim, this is also returned for files that are not ZIM archives.
+    ZimFileFormat(String),
+    /// The archive could not be read from the filesystem.
+    Io(String),
+    /// The archive has no full-text or title index to run this search against.
+    SearchUnavailable(String),
+    /// An argument was rejected by libzim (malformed uuid, empty query, ...).
+    InvalidArgument(String),
+    /// Following redirects came back to an entry already visited.
+    RedirectLoop(String),
+    /// A redirect points to an entry that does not exist.
+    DanglingRedirect(String),
+    /// A redirect chain is longer than the allowed depth.
+    TooManyRedirects(String),
+    /// libzim failed without a more specific kind.
+    Libzim(String),
+}
+
+impl Error {
+    /// The message attached to this error.
+    pub fn message(&self) -> &str {
+        match self {
+            Error::EntryNotFound(msg)
+            | Error::InvalidFormat(msg)
+            | Error::ZimFileFormat(msg)
+            | Error::Io(msg)
+            | Error::SearchUnavailable(msg)
+            | Error::InvalidArgument(msg)
+            | Error::RedirectLoop(msg)
+            | Error::DanglingRedirect(msg)
+            | Error::TooManyRedirects(msg)
+            | Error::Libzim(msg) => msg,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Error::EntryNotFound(_) => "entry not found",
+            Error::InvalidFormat(_) => "invalid format",
+            Error::ZimFileFormat(_) => "zim file format error",
+            Error::Io(_) => "io error",
+            Error::SearchUnavailable(_) => "search unavailable",
+            Error::InvalidArgument(_) => "invalid argument",
+            Error::RedirectLoop(_) => "redirect loop",
+            Error::DanglingRedirect(_) => "dangling redirect",
+            Error::TooManyRedirects(_) => "too many redirects",
+            Error::Libzim(_) => "libzim error",
+        };
+        write!(f, "{}: {}", kind, self.message())
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err.to_string())
+    }
+}
+
+#[cfg(not(feature = "pure-rust"))]
+impl From<cxx::Exception> for Error {
+    /// `binding.h` prefixes the message of every exception thrown by libzim with its
+    /// kind, followed by `KIND_SEPARATOR`.
+    fn from(err: cxx::Exception) -> Self {
+        let (kind, msg) = err
+            .what()
+            .split_once(KIND_SEPARATOR)
+            .unwrap_or(("", err.what()));
+        let msg = msg.to_string();
+        match kind {
+            "EntryNotFound" => Error::EntryNotFound(msg),
+            "ZimFileFormat" => Error::ZimFileFormat(msg),
+            "InvalidArgument" => Error::InvalidArgument(msg),
+            "Io" => Error::Io(msg),
+            _ => Error::Libzim(msg),
+        }
+    }
+}
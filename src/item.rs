@@ -1,17 +1,80 @@
+use std::{
+    io::{self, BufRead, Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+#[cfg(feature = "pure-rust")]
+use std::sync::Arc;
+
+#[cfg(not(feature = "pure-rust"))]
 use cxx::UniquePtr;
-use zim_sys::binding::ffi;
 
-use crate::{blob::Blob, cxx::string_from_ptr};
+use crate::{archive::Archive, blob::Blob};
+#[cfg(not(feature = "pure-rust"))]
+use crate::{binding::ffi, cxx::string_from_ptr, error::Error};
 
+/// Number of bytes an [`ItemReader`] fetches from the archive at a time by default.
+pub const DEFAULT_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Where the bytes of an uncompressed item are stored on disk, see [`Item::direct_access`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectAccess {
+    /// File holding the item, which is the relevant part for split archives.
+    pub path: PathBuf,
+    /// Offset of the first byte of the item in `path`; the item spans
+    /// [`Item::get_size`] bytes from there.
+    pub offset: u64,
+}
+
+#[cfg(not(feature = "pure-rust"))]
 pub struct Item {
     ptr: UniquePtr<ffi::Item>,
+    archive: Archive,
+}
+
+#[cfg(feature = "pure-rust")]
+pub struct Item {
+    index: u32,
+    dirent: Arc<crate::native::format::Dirent>,
+    archive: Archive,
+}
+
+// [SAFETY]
+// Item is readonly and keeps its parent archive alive through `archive`.
+// libzim itself is threadsafe, so it is valid to call libzim method from different threads.
+#[cfg(not(feature = "pure-rust"))]
+unsafe impl Send for Item {}
+
+#[cfg(feature = "pure-rust")]
+impl Item {
+    /// `dirent` must not be a redirect.
+    pub(crate) fn from_dirent(
+        index: u32,
+        dirent: Arc<crate::native::format::Dirent>,
+        archive: &Archive,
+    ) -> Item {
+        Item {
+            index,
+            dirent,
+            archive: archive.clone(),
+        }
+    }
+
+    pub(crate) fn dirent(&self) -> &Arc<crate::native::format::Dirent> {
+        &self.dirent
+    }
+
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
 }
 
+#[cfg(not(feature = "pure-rust"))]
 impl Item {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Item>) -> Result<Item, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Item { ptr }),
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Item>, archive: &Archive) -> Item {
+        Item {
+            ptr,
+            archive: archive.clone(),
         }
     }
 
@@ -20,30 +83,24 @@ impl Item {
     }
 
     pub fn get_title(&self) -> String {
-        let title = ffi::item_getTitle(self.inner_ref());
-        title
-            .as_ref()
-            .expect("Item::get_title should be infallible")
-            .to_string()
+        string_from_ptr(ffi::item_getTitle(self.inner_ref()))
     }
 
     pub fn get_path(&self) -> String {
-        let path = ffi::item_getPath(self.inner_ref());
-        path.as_ref()
-            .expect("Item::get_path should be infallible")
-            .to_string()
+        string_from_ptr(ffi::item_getPath(self.inner_ref()))
     }
 
-    pub fn get_mimetype(&self) -> Result<String, ()> {
-        string_from_ptr(ffi::item_getMimetype(self.inner_ref()))
+    pub fn get_mimetype(&self) -> Result<String, Error> {
+        Ok(string_from_ptr(ffi::item_getMimetype(self.inner_ref())?))
     }
 
-    pub fn get_data(&self) -> Result<Blob, ()> {
-        Blob::from_ptr(ffi::item_getData(self.inner_ref()))
+    pub fn get_data(&self) -> Result<Blob, Error> {
+        Ok(Blob::from_ptr(ffi::item_getData(self.inner_ref())?))
     }
 
-    pub fn get_data_offset(&self, offset: u64, size: u64) -> Result<Blob, ()> {
-        Blob::from_ptr(ffi::item_getData_offset(self.inner_ref(), offset, size))
+    pub fn get_data_offset(&self, offset: u64, size: u64) -> Result<Blob, Error> {
+        let blob = ffi::item_getData_offset(self.inner_ref(), offset, size)?;
+        Ok(Blob::from_ptr(blob))
     }
 
     pub fn get_size(&self) -> u64 {
@@ -53,4 +110,145 @@ impl Item {
     pub fn get_index(&self) -> u32 {
         ffi::item_getIndex(self.inner_ref())
     }
+
+    /// The location of the item's bytes on disk, for serving them without copying
+    /// (`sendfile`, `mmap`, ...).
+    ///
+    /// `None` if the item is stored in a compressed cluster, or if the archive was
+    /// not opened from a file path.
+    pub fn direct_access(&self) -> Option<DirectAccess> {
+        let path = ffi::item_getDirectAccessPath(self.inner_ref()).ok()?;
+        let path = path.as_ref().filter(|path| !path.is_empty())?;
+        #[cfg(unix)]
+        let path = {
+            use std::os::unix::ffi::OsStrExt;
+            PathBuf::from(std::ffi::OsStr::from_bytes(path.as_bytes()))
+        };
+        #[cfg(not(unix))]
+        let path = PathBuf::from(path.to_string_lossy().into_owned());
+        Some(DirectAccess {
+            path,
+            offset: ffi::item_getDirectAccessOffset(self.inner_ref()).ok()?,
+        })
+    }
+}
+
+impl Item {
+    /// A streaming reader over the item's content, holding at most one chunk of
+    /// [`DEFAULT_CHUNK_SIZE`] bytes in memory.
+    pub fn reader(self) -> ItemReader {
+        ItemReader::new(self, DEFAULT_CHUNK_SIZE)
+    }
+
+    /// The archive this item was read from.
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+}
+
+/// [`Read`], [`BufRead`] and [`Seek`] over the content of an [`Item`], fetched in
+/// bounded chunks through [`Item::get_data_offset`].
+///
+/// The reader owns its item, so it can be handed to another thread or kept
+/// around while the content is served.
+pub struct ItemReader {
+    item: Item,
+    size: u64,
+    chunk_size: u64,
+    pos: u64,
+    chunk: Option<Blob>,
+    chunk_start: u64,
+}
+
+impl ItemReader {
+    pub fn new(item: Item, chunk_size: u64) -> ItemReader {
+        ItemReader {
+            size: item.get_size(),
+            item,
+            chunk_size: chunk_size.max(1),
+            pos: 0,
+            chunk: None,
+            chunk_start: 0,
+        }
+    }
+
+    /// Size of the whole item.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn item(&self) -> &Item {
+        &self.item
+    }
+
+    pub fn into_item(self) -> Item {
+        self.item
+    }
+
+    fn chunk_contains(&self, pos: u64) -> bool {
+        match &self.chunk {
+            Some(chunk) => (self.chunk_start..self.chunk_start + chunk.size()).contains(&pos),
+            None => false,
+        }
+    }
+}
+
+impl BufRead for ItemReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.size {
+            return Ok(&[]);
+        }
+        if !self.chunk_contains(self.pos) {
+            let len = self.chunk_size.min(self.size - self.pos);
+            let chunk = self
+                .item
+                .get_data_offset(self.pos, len)
+                .map_err(io::Error::other)?;
+            if chunk.size() == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("no data at offset {}", self.pos),
+                ));
+            }
+            self.chunk = Some(chunk);
+            self.chunk_start = self.pos;
+        }
+        let start = (self.pos - self.chunk_start) as usize;
+        Ok(&self.chunk.as_ref().unwrap().data()[start..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt as u64).min(self.size);
+    }
+}
+
+impl Read for ItemReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl Seek for ItemReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(delta) => self.size.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        match pos {
+            // Seeking past the end is allowed, reads there return nothing.
+            Some(pos) => {
+                self.pos = pos;
+                Ok(pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
 }
@@ -1,13 +1,30 @@
-#![allow(clippy::result_unit_err)] // TODO: Fix Result return types...
+#[cfg(not(any(feature = "libzim", feature = "pure-rust")))]
+compile_error!("either the `libzim` or the `pure-rust` feature must be enabled");
+
+#[cfg(not(feature = "pure-rust"))]
+mod binding;
+#[cfg(not(feature = "pure-rust"))]
 pub(crate) mod cxx;
+#[cfg(feature = "pure-rust")]
+mod native;
 
 pub mod archive;
 pub mod blob;
+#[cfg(not(feature = "pure-rust"))]
+pub mod cursor;
 pub mod entry;
+pub mod error;
 pub mod item;
+pub mod metadata;
+#[cfg(not(feature = "pure-rust"))]
 pub mod search;
+#[cfg(not(feature = "pure-rust"))]
 pub mod suggestion;
 pub mod uuid;
+#[cfg(not(feature = "pure-rust"))]
+pub mod writer;
+
+pub use error::Error;
 
 #[cfg(test)]
 mod test;
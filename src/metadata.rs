@@ -0,0 +1,311 @@
+use std::{collections::BTreeMap, convert::Infallible, fmt, str::FromStr};
+
+use crate::{archive::Archive, error::Error};
+
+/// The standard `M/` metadata of an archive, parsed into typed fields.
+///
+/// Fields are `None` (or empty) when the archive does not set them. Keys without a
+/// dedicated field, or whose value could not be parsed, are kept as-is in `other`.
+/// Binary `Illustration_*` keys are left out, see [`Archive::get_illustration_item`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ArchiveMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub long_description: Option<String>,
+    /// ISO 639-3 codes, most prominent language first.
+    pub language: Vec<String>,
+    pub creator: Option<String>,
+    pub publisher: Option<String>,
+    pub date: Option<Date>,
+    pub name: Option<String>,
+    pub flavour: Option<String>,
+    pub source: Option<String>,
+    pub license: Option<String>,
+    pub relation: Option<String>,
+    pub tags: Option<Tags>,
+    pub counter: Option<Counter>,
+    pub other: BTreeMap<String, String>,
+}
+
+impl ArchiveMetadata {
+    pub(crate) fn from_archive(archive: &Archive) -> Result<ArchiveMetadata, Error> {
+        let mut metadata = ArchiveMetadata::default();
+        for key in archive.get_metadata_keys()? {
+            if key.starts_with("Illustration_") {
+                continue;
+            }
+            let value = archive.get_metadata(&key)?;
+            metadata.set(key, value);
+        }
+        Ok(metadata)
+    }
+
+    fn set(&mut self, key: String, value: String) {
+        let field = match key.as_str() {
+            "Title" => &mut self.title,
+            "Description" => &mut self.description,
+            "LongDescription" => &mut self.long_description,
+            "Creator" => &mut self.creator,
+            "Publisher" => &mut self.publisher,
+            "Name" => &mut self.name,
+            "Flavour" => &mut self.flavour,
+            "Source" => &mut self.source,
+            "License" => &mut self.license,
+            "Relation" => &mut self.relation,
+            "Language" => {
+                self.language = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|code| !code.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                return;
+            }
+            "Date" => {
+                match value.parse() {
+                    Ok(date) => self.date = Some(date),
+                    Err(_) => {
+                        self.other.insert(key, value);
+                    }
+                }
+                return;
+            }
+            "Tags" => {
+                self.tags = value.parse().ok();
+                return;
+            }
+            "Counter" => {
+                match value.parse() {
+                    Ok(counter) => self.counter = Some(counter),
+                    Err(_) => {
+                        self.other.insert(key, value);
+                    }
+                }
+                return;
+            }
+            _ => {
+                self.other.insert(key, value);
+                return;
+            }
+        };
+        *field = Some(value);
+    }
+}
+
+/// A calendar date as stored in the `Date` metadata (`YYYY-MM-DD`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// `None` if the day does not exist in the given month and year.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Date> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        match (1..=days).contains(&day) {
+            true => Some(Date { year, month, day }),
+            false => None,
+        }
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+impl FromStr for Date {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidArgument(format!("{:?} is not a YYYY-MM-DD date", s));
+        let mut parts = s.trim().splitn(3, '-');
+        let mut next = |len: usize| {
+            parts
+                .next()
+                .filter(|part| part.len() == len && part.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|part| part.parse::<u16>().ok())
+                .ok_or_else(invalid)
+        };
+        let year = next(4)?;
+        let month = next(2)?;
+        let day = next(2)?;
+        Date::new(year, month as u8, day as u8).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// The `Tags` metadata: `;` separated tags, where tags starting with `_` are
+/// well-known `_name:value` pairs (`_ftindex:yes`, `_category:wikipedia`, ...) and
+/// the others are free-form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tags {
+    tags: Vec<String>,
+}
+
+impl Tags {
+    /// Value of the well-known tag `_name`, `name` being given without the underscore.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find_map(|tag| {
+            let (key, value) = tag.strip_prefix('_')?.split_once(':')?;
+            (key == name).then_some(value)
+        })
+    }
+
+    fn flag(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            "yes" => Some(true),
+            "no" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// `_ftindex`: whether the archive has a full-text index.
+    pub fn ftindex(&self) -> Option<bool> {
+        self.flag("ftindex")
+    }
+
+    /// `_pictures`: whether pictures were kept.
+    pub fn pictures(&self) -> Option<bool> {
+        self.flag("pictures")
+    }
+
+    /// `_videos`: whether videos were kept.
+    pub fn videos(&self) -> Option<bool> {
+        self.flag("videos")
+    }
+
+    /// `_details`: whether articles are complete rather than introductions only.
+    pub fn details(&self) -> Option<bool> {
+        self.flag("details")
+    }
+
+    /// `_category`: the kind of content, such as `wikipedia` or `gutenberg`.
+    pub fn category(&self) -> Option<&str> {
+        self.get("category")
+    }
+
+    /// Free-form tags, those not starting with `_`.
+    pub fn free(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .filter(|tag| !tag.starts_with('_'))
+            .map(String::as_str)
+    }
+
+    /// Every tag, as written in the metadata.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+}
+
+impl FromStr for Tags {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tags = s
+            .split(';')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Tags { tags })
+    }
+}
+
+impl fmt::Display for Tags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tags.join(";"))
+    }
+}
+
+/// The `Counter` metadata: number of entries per mimetype, written as
+/// `text/html=1234;image/png=56`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counter {
+    counts: BTreeMap<String, u64>,
+}
+
+impl Counter {
+    /// Number of entries with `mimetype`, 0 if there are none.
+    pub fn get(&self, mimetype: &str) -> u64 {
+        self.counts.get(mimetype).copied().unwrap_or(0)
+    }
+
+    /// Number of entries of all mimetypes.
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// `(mimetype, count)` pairs, sorted by mimetype.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.counts
+            .iter()
+            .map(|(mimetype, count)| (mimetype.as_str(), *count))
+    }
+}
+
+impl FromStr for Counter {
+    type Err = Error;
+
+    /// Mimetypes may have parameters (`text/html;raw=true=5`), so a segment that does
+    /// not end with `=count` is part of the mimetype of the next one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut counts = BTreeMap::new();
+        let mut pair = String::new();
+        for segment in s.split(';') {
+            match pair.trim().is_empty() {
+                true => pair.clear(),
+                false => pair.push(';'),
+            }
+            pair.push_str(segment);
+            let Some((mimetype, count)) = pair
+                .rsplit_once('=')
+                .and_then(|(mimetype, count)| Some((mimetype, count.trim().parse::<u64>().ok()?)))
+            else {
+                continue;
+            };
+            *counts.entry(mimetype.trim().to_string()).or_insert(0) += count;
+            pair.clear();
+        }
+        match pair.trim().is_empty() {
+            true => Ok(Counter { counts }),
+            false => Err(Error::InvalidArgument(format!(
+                "{:?} is not a mimetype=count pair",
+                pair.trim()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs = self
+            .iter()
+            .map(|(mimetype, count)| format!("{}={}", mimetype, count))
+            .collect::<Vec<_>>();
+        write!(f, "{}", pairs.join(";"))
+    }
+}
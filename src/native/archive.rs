@@ -0,0 +1,613 @@
+use std::{
+    fs::File,
+    io,
+    ops::{Range, RangeBounds},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+#[cfg(unix)]
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
+
+use crate::{
+    archive::{discover_parts, resolve_range, Archive, ArchivePart, OpenConfig},
+    entry::Entry,
+    error::Error,
+    item::Item,
+    native::format::{PartFile, Reader, DEFAULT_LOOKUP_SIZE},
+    uuid::Uuid,
+};
+
+impl Archive {
+    pub fn new(path: &str) -> Result<Archive, Error> {
+        Archive::open_path(Path::new(path), DEFAULT_LOOKUP_SIZE)
+    }
+
+    /// Open the archive at `path` with explicit cache sizes and preloading, see [`OpenConfig`].
+    pub fn open_with_config<P: AsRef<Path>>(
+        path: P,
+        config: &OpenConfig,
+    ) -> Result<Archive, Error> {
+        let lookup_size = config.preload_dirents.unwrap_or(DEFAULT_LOOKUP_SIZE);
+        let archive = Archive::open_path(path.as_ref(), lookup_size)?;
+        config.apply(&archive);
+        Ok(archive)
+    }
+
+    fn open_path(path: &Path, lookup_size: u64) -> Result<Archive, Error> {
+        let parts = discover_parts(path)?;
+        let files = parts
+            .into_iter()
+            .map(|part| {
+                let part_path = part.path.unwrap();
+                let file = File::open(&part_path)?;
+                Ok((file, Some(part_path), 0, part.range.end))
+            })
+            .collect::<Result<Vec<_>, io::Error>>()
+            .map_err(|err| Error::Io(format!("{}: {}", path.display(), err)))?;
+        let filename = path.to_string_lossy().into_owned();
+        Archive::open_files(files, filename, &path.display().to_string(), lookup_size)
+    }
+
+    /// Open the archive stored in `(file, path, offset, size)` parts, preloading
+    /// `lookup_size` dirents in the path lookup table.
+    fn open_files(
+        files: Vec<(File, Option<PathBuf>, u64, u64)>,
+        filename: String,
+        name: &str,
+        lookup_size: u64,
+    ) -> Result<Archive, Error> {
+        let parts = files
+            .iter()
+            .map(|(_, path, offset, size)| ArchivePart {
+                path: path.clone(),
+                range: *offset..*offset + *size,
+            })
+            .collect();
+        let files = files
+            .into_iter()
+            .map(|(file, path, offset, size)| PartFile {
+                file,
+                path,
+                offset,
+                size,
+            })
+            .collect();
+        let reader = Reader::open(files, filename, name, lookup_size)?;
+        Ok(Archive::from_reader(reader, parts))
+    }
+
+    /// Open the archive from an already opened file descriptor.
+    ///
+    /// The descriptor is duplicated, so `fd` can be closed once this returns.
+    #[cfg(unix)]
+    pub fn from_fd<F: AsFd>(fd: F) -> Result<Archive, Error> {
+        let fd = fd.as_fd();
+        let size = File::from(fd.try_clone_to_owned()?).metadata()?.len();
+        Archive::from_fd_range(fd, 0, size)
+    }
+
+    /// Open an archive stored as the `size` bytes starting at `offset` of a larger file,
+    /// as found in bundles and Android assets.
+    ///
+    /// The descriptor is duplicated, so `fd` can be closed once this returns.
+    #[cfg(unix)]
+    pub fn from_fd_range<F: AsFd>(fd: F, offset: u64, size: u64) -> Result<Archive, Error> {
+        Archive::open_part_fds(&[(fd.as_fd(), offset, size)])
+    }
+
+    /// Open a split archive from its parts, in order, whatever their names or directories.
+    ///
+    /// Unlike [`Archive::new`], this does not rely on the `.zimaa`, `.zimab`, ... naming
+    /// convention to find the parts.
+    #[cfg(unix)]
+    pub fn open_parts<P: AsRef<Path>>(paths: &[P]) -> Result<Archive, Error> {
+        let files = paths
+            .iter()
+            .map(|path| {
+                let path = path.as_ref();
+                let file = File::open(path)?;
+                let size = file.metadata()?.len();
+                Ok((file, Some(path.to_path_buf()), 0, size))
+            })
+            .collect::<Result<Vec<_>, io::Error>>()?;
+        let name = match paths.first() {
+            Some(path) => path.as_ref().display().to_string(),
+            None => return Err(Error::InvalidArgument("no archive part given".to_string())),
+        };
+        Archive::open_files(files, String::new(), &name, DEFAULT_LOOKUP_SIZE)
+    }
+
+    /// Open a split archive from `(fd, offset, size)` parts, in order.
+    ///
+    /// The descriptors are duplicated, so they can be closed once this returns.
+    #[cfg(unix)]
+    pub fn open_part_fds(parts: &[(BorrowedFd<'_>, u64, u64)]) -> Result<Archive, Error> {
+        let name = match parts.first() {
+            Some((fd, offset, _)) => format!("fd {} at offset {}", fd.as_raw_fd(), offset),
+            None => return Err(Error::InvalidArgument("no archive part given".to_string())),
+        };
+        let files = parts
+            .iter()
+            .map(|(fd, offset, size)| {
+                Ok((File::from(fd.try_clone_to_owned()?), None, *offset, *size))
+            })
+            .collect::<Result<Vec<_>, io::Error>>()?;
+        Archive::open_files(files, String::new(), &name, DEFAULT_LOOKUP_SIZE)
+    }
+
+    pub(crate) fn entry_at(&self, index: u32) -> Result<Entry, Error> {
+        Ok(Entry::from_dirent(
+            index,
+            self.reader().dirent(index)?,
+            self,
+        ))
+    }
+
+    /// The item of the entry `path` of `namespace`, following redirects.
+    fn find_item(&self, namespace: u8, path: &str) -> Result<Option<Item>, Error> {
+        match self.reader().find(namespace, path)? {
+            Some(index) => self.entry_at(index)?.get_item(true).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_filename(&self) -> String {
+        self.reader().filename().to_string()
+    }
+
+    pub fn get_filesize(&self) -> u64 {
+        self.reader().size()
+    }
+
+    pub fn get_all_entrycount(&self) -> u32 {
+        self.reader().entry_count()
+    }
+
+    pub fn get_entrycount(&self) -> u32 {
+        self.reader()
+            .user_range()
+            .map_or(0, |range| range.len() as u32)
+    }
+
+    pub fn get_articlecount(&self) -> u32 {
+        match self.reader().article_count() {
+            Some(count) => count,
+            None => self.article_range().map_or(0, |range| range.len() as u32),
+        }
+    }
+
+    /// Entries considered articles when the archive does not list its front articles.
+    fn article_range(&self) -> Result<Range<u32>, Error> {
+        match self.has_new_namespace_scheme() {
+            true => self.reader().namespace_range(b'C'),
+            false => self.reader().namespace_range(b'A'),
+        }
+    }
+
+    pub fn get_uuid(&self) -> Result<Uuid, Error> {
+        Ok(Uuid::from_bytes(self.reader().uuid()))
+    }
+
+    pub fn get_metadata(&self, name: &str) -> Result<String, Error> {
+        let item = self.get_metadata_item(name)?;
+        Ok(String::from_utf8_lossy(item.get_data()?.data()).into_owned())
+    }
+
+    pub fn get_metadata_item(&self, name: &str) -> Result<Item, Error> {
+        self.find_item(b'M', name)?
+            .ok_or_else(|| Error::EntryNotFound(format!("no metadata named {}", name)))
+    }
+
+    pub fn get_metadata_keys(&self) -> Result<Vec<String>, Error> {
+        self.reader()
+            .namespace_range(b'M')?
+            .map(|index| Ok(self.reader().dirent(index)?.path.clone()))
+            .collect()
+    }
+
+    /// Sizes (width and height, illustrations are square) of the illustrations
+    /// stored in the archive, in increasing order.
+    pub fn get_illustration_sizes(&self) -> Result<Vec<u32>, Error> {
+        let mut sizes = self
+            .get_metadata_keys()?
+            .iter()
+            .filter_map(|key| illustration_size(key))
+            .collect::<Vec<u32>>();
+        if !sizes.contains(&48) && self.reader().find(b'-', "favicon")?.is_some() {
+            sizes.push(48);
+        }
+        sizes.sort_unstable();
+        sizes.dedup();
+        Ok(sizes)
+    }
+
+    pub fn has_illustration(&self, size: u32) -> bool {
+        self.get_illustration_item(size).is_ok()
+    }
+
+    /// The PNG illustration of `size`x`size` pixels, 48 being the favicon every archive
+    /// should have.
+    pub fn get_illustration_item(&self, size: u32) -> Result<Item, Error> {
+        let mut item = self.find_item(b'M', &format!("Illustration_{0}x{0}@1", size))?;
+        if item.is_none() && size == 48 {
+            item = self.find_item(b'-', "favicon")?;
+        }
+        item.ok_or_else(|| Error::EntryNotFound(format!("no {0}x{0} illustration", size)))
+    }
+
+    pub fn get_entry_bypath_index(&self, index: u32) -> Result<Entry, Error> {
+        match index < self.reader().entry_count() {
+            true => self.entry_at(index),
+            false => Err(Error::EntryNotFound(format!(
+                "no entry at path index {}",
+                index
+            ))),
+        }
+    }
+
+    /// With the old namespace scheme, `path` is `<namespace>/<path>`; a path without
+    /// namespace is looked up in the `A`, `I`, `J` and `-` namespaces.
+    pub fn get_entry_bypath_str(&self, path: &str) -> Result<Entry, Error> {
+        let reader = self.reader();
+        let index = match self.has_new_namespace_scheme() {
+            true => reader.find(b'C', path)?,
+            false => match split_namespace(path) {
+                Some((namespace, path)) => reader.find(namespace, path)?,
+                None => b"AIJ-"
+                    .iter()
+                    .map(|&namespace| reader.find(namespace, path))
+                    .find_map(Result::transpose)
+                    .transpose()?,
+            },
+        };
+        match index {
+            Some(index) => self.entry_at(index),
+            None => Err(Error::EntryNotFound(format!("no entry with path {}", path))),
+        }
+    }
+
+    pub fn get_entry_bytitle_index(&self, index: u32) -> Result<Entry, Error> {
+        self.entry_at(self.reader().title_entry(index)?)
+    }
+
+    pub fn get_entry_bytitle_str(&self, title: &str) -> Result<Entry, Error> {
+        match self.reader().find_by_title(self.title_namespace(), title)? {
+            Some(index) => self.entry_at(index),
+            None => Err(Error::EntryNotFound(format!(
+                "no entry with title {}",
+                title
+            ))),
+        }
+    }
+
+    fn title_namespace(&self) -> u8 {
+        match self.has_new_namespace_scheme() {
+            true => b'C',
+            false => b'A',
+        }
+    }
+
+    /// `W/mainPage` if the archive has it, else the main page of the header.
+    fn main_entry_index(&self) -> Result<Option<u32>, Error> {
+        match self.reader().find(b'W', "mainPage")? {
+            Some(index) => Ok(Some(index)),
+            None => Ok(self.reader().main_page()),
+        }
+    }
+
+    pub fn get_mainentry(&self) -> Result<Entry, Error> {
+        match self.main_entry_index()? {
+            Some(index) => self.get_entry_bypath_index(index),
+            None => Err(Error::EntryNotFound(
+                "archive has no main entry".to_string(),
+            )),
+        }
+    }
+
+    pub fn get_randomentry(&self) -> Result<Entry, Error> {
+        let none = || Error::EntryNotFound("archive has no article to pick from".to_string());
+        let reader = self.reader();
+        let index = match reader.article_count() {
+            Some(0) => return Err(none()),
+            Some(count) => reader
+                .article_entry(random_below(count))?
+                .ok_or_else(none)?,
+            None => {
+                let range = self.article_range()?;
+                if range.is_empty() {
+                    return Err(none());
+                }
+                range.start + random_below(range.len() as u32)
+            }
+        };
+        self.entry_at(index)
+    }
+
+    pub fn has_entry_bypath(&self, path: &str) -> bool {
+        self.get_entry_bypath_str(path).is_ok()
+    }
+
+    pub fn has_entry_bytitle(&self, title: &str) -> bool {
+        self.get_entry_bytitle_str(title).is_ok()
+    }
+
+    pub fn has_mainentry(&self) -> bool {
+        matches!(self.main_entry_index(), Ok(Some(_)))
+    }
+
+    pub fn has_fulltext_index(&self) -> bool {
+        let index = match self.has_new_namespace_scheme() {
+            true => self.reader().find(b'X', "fulltext/xapian"),
+            false => self.reader().find(b'Z', "/fulltextIndex/xapian"),
+        };
+        matches!(index, Ok(Some(_)))
+    }
+
+    pub fn has_checksum(&self) -> bool {
+        self.reader().has_checksum()
+    }
+
+    pub fn get_checksum(&self) -> Result<String, Error> {
+        match self.reader().checksum()? {
+            Some(checksum) => Ok(checksum.iter().map(|b| format!("{:02x}", b)).collect()),
+            None => Err(Error::EntryNotFound("archive has no checksum".to_string())),
+        }
+    }
+
+    pub fn check(&self) -> bool {
+        self.reader().verify_checksum().unwrap_or(false)
+    }
+
+    pub fn is_multipart(&self) -> bool {
+        self.reader().part_count() > 1
+    }
+
+    pub fn has_new_namespace_scheme(&self) -> bool {
+        self.reader().has_new_namespace_scheme()
+    }
+
+    /// Maximum number of dirents kept in memory.
+    pub fn get_dirent_cache_max_size(&self) -> u64 {
+        self.reader().dirent_cache_max_size()
+    }
+
+    /// Number of dirents currently kept in memory.
+    pub fn get_dirent_cache_current_size(&self) -> u64 {
+        self.reader().dirent_cache_current_size()
+    }
+
+    /// Change the maximum number of cached dirents, evicting dirents if the cache shrinks.
+    pub fn set_dirent_cache_max_size(&self, nb_dirents: u64) {
+        self.reader().set_dirent_cache_max_size(nb_dirents)
+    }
+
+    /// Number of dirents preloaded in the path lookup table.
+    pub fn get_dirent_lookup_cache_max_size(&self) -> u64 {
+        self.reader().lookup_size()
+    }
+
+    /// All entries in cluster order, which is the fastest way to read every item.
+    ///
+    /// Reads every dirent the first time it is called, to sort them.
+    pub fn iter_efficient(&self) -> Result<EntryRangeEfficient, Error> {
+        let len = self.reader().efficient_order()?.len();
+        Ok(EntryRangeEfficient::new(self, 0..len))
+    }
+
+    /// All entries sorted by path.
+    pub fn iter_by_path(&self) -> Result<EntryRangePath, Error> {
+        let range = self.reader().user_range()?;
+        Ok(EntryRangePath::new(
+            self,
+            range.start as usize..range.end as usize,
+        ))
+    }
+
+    /// All entries sorted by title.
+    pub fn iter_by_title(&self) -> Result<EntryRangeTitle, Error> {
+        let range = match self.has_new_namespace_scheme() {
+            true => self.reader().title_prefix_range(b'C', "")?,
+            false => 0..self.reader().title_count(),
+        };
+        Ok(EntryRangeTitle::new(
+            self,
+            range.start as usize..range.end as usize,
+        ))
+    }
+
+    /// Entries whose path starts with `prefix`, sorted by path.
+    pub fn find_by_path(&self, prefix: &str) -> Result<EntryRangePath, Error> {
+        let reader = self.reader();
+        let range = match self.has_new_namespace_scheme() {
+            true => reader.path_prefix_range(b'C', prefix)?,
+            false if prefix.is_empty() => 0..reader.entry_count(),
+            false if prefix.len() == 1 => reader.namespace_range(prefix.as_bytes()[0])?,
+            false => match split_namespace(prefix) {
+                Some((namespace, prefix)) => reader.path_prefix_range(namespace, prefix)?,
+                None => 0..0,
+            },
+        };
+        Ok(EntryRangePath::new(
+            self,
+            range.start as usize..range.end as usize,
+        ))
+    }
+
+    /// Entries whose title starts with `prefix`, sorted by title.
+    pub fn find_by_title(&self, prefix: &str) -> Result<EntryRangeTitle, Error> {
+        let range = self
+            .reader()
+            .title_prefix_range(self.title_namespace(), prefix)?;
+        Ok(EntryRangeTitle::new(
+            self,
+            range.start as usize..range.end as usize,
+        ))
+    }
+}
+
+/// Split `<namespace>/<path>`.
+fn split_namespace(path: &str) -> Option<(u8, &str)> {
+    match path.as_bytes() {
+        [namespace, b'/', ..] => Some((*namespace, &path[2..])),
+        _ => None,
+    }
+}
+
+/// The size of an `Illustration_<size>x<size>@1` metadata key.
+fn illustration_size(key: &str) -> Option<u32> {
+    let size = key.strip_prefix("Illustration_")?.strip_suffix("@1")?;
+    let (width, height) = size.split_once('x')?;
+    let width = width.parse().ok()?;
+    (height.parse() == Ok(width)).then_some(width)
+}
+
+/// A random number below `bound`, from a xorshift generator seeded with the time.
+fn random_below(bound: u32) -> u32 {
+    static STATE: AtomicU64 = AtomicU64::new(0);
+    let mut x = STATE.load(Ordering::Relaxed);
+    if x == 0 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        x = now.as_nanos() as u64 | 1;
+    }
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    STATE.store(x, Ordering::Relaxed);
+    (x % bound as u64) as u32
+}
+
+/// Declare a lazy range of entries and its iterator, `$entry` reading the entry at
+/// a position of the range.
+///
+/// Positions are plain indexes into the range, so sizing, skipping, slicing and
+/// reverse iteration never walk through the entries in between.
+macro_rules! entry_range {
+    (
+        $(#[$meta:meta])*
+        $range:ident, $iter:ident, $entry:expr
+    ) => {
+        $(#[$meta])*
+        pub struct $range {
+            archive: Archive,
+            start: usize,
+            end: usize,
+        }
+
+        impl $range {
+            fn new(archive: &Archive, range: Range<usize>) -> $range {
+                $range {
+                    archive: archive.clone(),
+                    start: range.start,
+                    end: range.end,
+                }
+            }
+
+            pub fn len(&self) -> usize {
+                self.end - self.start
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// The entries at positions `range` of this range, in the same order.
+            pub fn range<R: RangeBounds<usize>>(&self, range: R) -> Result<$range, Error> {
+                let (start, end) = resolve_range(range, self.len())?;
+                Ok($range::new(
+                    &self.archive,
+                    self.start + start..self.start + end,
+                ))
+            }
+        }
+
+        impl IntoIterator for $range {
+            type Item = Result<Entry, Error>;
+            type IntoIter = $iter;
+
+            fn into_iter(self) -> Self::IntoIter {
+                $iter {
+                    front: self.start,
+                    back: self.end,
+                    archive: self.archive,
+                }
+            }
+        }
+
+        pub struct $iter {
+            front: usize,
+            back: usize,
+            archive: Archive,
+        }
+
+        impl $iter {
+            fn entry_at(&self, pos: usize) -> Result<Entry, Error> {
+                let entry: fn(&Archive, usize) -> Result<Entry, Error> = $entry;
+                entry(&self.archive, pos)
+            }
+        }
+
+        impl Iterator for $iter {
+            type Item = Result<Entry, Error>;
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.front < self.back {
+                    self.front += 1;
+                    Some(self.entry_at(self.front - 1))
+                } else {
+                    None
+                }
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.back - self.front;
+                (len, Some(len))
+            }
+
+            fn nth(&mut self, n: usize) -> Option<Self::Item> {
+                self.front = self.front.saturating_add(n).min(self.back);
+                self.next()
+            }
+        }
+
+        impl DoubleEndedIterator for $iter {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                if self.front < self.back {
+                    self.back -= 1;
+                    Some(self.entry_at(self.back))
+                } else {
+                    None
+                }
+            }
+
+            fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+                self.back = self.back.saturating_sub(n).max(self.front);
+                self.next_back()
+            }
+        }
+
+        impl ExactSizeIterator for $iter {}
+    };
+}
+
+entry_range!(
+    /// Entries in cluster order, see [`Archive::iter_efficient`].
+    EntryRangeEfficient,
+    IterEfficient,
+    |archive, pos| archive.entry_at(archive.reader().efficient_order()?[pos])
+);
+
+entry_range!(
+    /// Entries in path order, see [`Archive::iter_by_path`] and [`Archive::find_by_path`].
+    EntryRangePath,
+    IterPath,
+    |archive, pos| archive.entry_at(pos as u32)
+);
+
+entry_range!(
+    /// Entries in title order, see [`Archive::iter_by_title`] and [`Archive::find_by_title`].
+    EntryRangeTitle,
+    IterTitle,
+    |archive, pos| archive.entry_at(archive.reader().title_entry(pos as u32)?)
+);
@@ -0,0 +1,58 @@
+use crate::{entry::Entry, error::Error, item::Item, native::format::Target};
+
+/// Redirects followed by [`Entry::get_redirect`] before giving up, like libzim.
+const MAX_REDIRECTS: usize = 50;
+
+impl Entry {
+    pub fn is_redirect(&self) -> bool {
+        self.dirent().is_redirect()
+    }
+
+    pub fn get_title(&self) -> String {
+        self.dirent().title().to_string()
+    }
+
+    pub fn get_path(&self) -> String {
+        match self.archive().has_new_namespace_scheme() {
+            true => self.dirent().path.clone(),
+            false => self.dirent().long_path(),
+        }
+    }
+
+    pub fn get_item(&self, follow: bool) -> Result<Item, Error> {
+        match (self.is_redirect(), follow) {
+            (false, _) => Ok(Item::from_dirent(
+                self.get_index(),
+                self.dirent().clone(),
+                self.archive(),
+            )),
+            (true, true) => self.get_redirect(),
+            (true, false) => Err(Error::InvalidArgument(format!(
+                "entry {} is a redirect",
+                self.get_path()
+            ))),
+        }
+    }
+
+    pub fn get_redirect(&self) -> Result<Item, Error> {
+        let mut entry = self.get_redirect_entry()?;
+        for _ in 0..MAX_REDIRECTS {
+            if !entry.is_redirect() {
+                return entry.get_item(false);
+            }
+            entry = entry.get_redirect_entry()?;
+        }
+        Err(Error::TooManyRedirects(format!(
+            "more than {} redirects from {}",
+            MAX_REDIRECTS,
+            self.get_path()
+        )))
+    }
+
+    pub fn get_redirect_entry(&self) -> Result<Entry, Error> {
+        match self.dirent().target {
+            Target::Redirect(index) => self.archive().get_entry_bypath_index(index),
+            _ => Err(self.not_a_redirect()),
+        }
+    }
+}
@@ -0,0 +1,1145 @@
+//! The ZIM file format itself: header, MIME list, pointer lists, dirents and clusters,
+//! as described on <https://wiki.openzim.org/wiki/ZIM_file_format>.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    fs::File,
+    hash::Hash,
+    io::{self, Read},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, LazyLock, Mutex, OnceLock,
+    },
+};
+
+use crate::{blob::Blob, error::Error};
+
+/// Magic number at the start of every ZIM file.
+const ZIM_MAGIC: u32 = 0x044D_495A;
+
+const HEADER_SIZE: usize = 80;
+
+/// `mainPage` of archives without a main page.
+const NO_MAIN_PAGE: u32 = u32::MAX;
+
+/// Dirent mimetypes that are not indexes into the MIME list.
+const MIMETYPE_REDIRECT: u16 = 0xffff;
+const MIMETYPE_LINK_TARGET: u16 = 0xfffe;
+const MIMETYPE_DELETED: u16 = 0xfffd;
+
+/// Same defaults as libzim, the cluster cache size being in bytes.
+pub(crate) const DEFAULT_CLUSTER_CACHE_SIZE: u64 = 512 << 20;
+pub(crate) const DEFAULT_DIRENT_CACHE_SIZE: u64 = 512;
+pub(crate) const DEFAULT_LOOKUP_SIZE: u64 = 1024;
+
+/// Bytes read at once when looking for the end of a dirent's path and title.
+const DIRENT_READ_SIZE: usize = 256;
+
+/// Bytes read at once while decompressing a cluster.
+const CLUSTER_READ_SIZE: usize = 64 << 10;
+
+/// Classify an open failure from the first bytes of the archive `source`.
+fn diagnose_header<D: Display>(source: D, read: io::Result<()>, header: &[u8; 8]) -> Error {
+    if let Err(err) = read {
+        return match err.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                Error::InvalidFormat(format!("{}: file is too small to be a ZIM archive", source))
+            }
+            _ => Error::Io(format!("{}: {}", source, err)),
+        };
+    }
+    let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+    let major = u16::from_le_bytes([header[4], header[5]]);
+    if magic != ZIM_MAGIC {
+        Error::InvalidFormat(format!("{}: invalid magic number {:#010x}", source, magic))
+    } else if major != 5 && major != 6 {
+        Error::ZimFileFormat(format!(
+            "{}: unsupported ZIM major version {}",
+            source, major
+        ))
+    } else {
+        Error::ZimFileFormat(format!("{}: failed to read the archive", source))
+    }
+}
+
+/// The fixed size header at the start of every archive.
+struct Header {
+    minor: u16,
+    uuid: [u8; 16],
+    entry_count: u32,
+    cluster_count: u32,
+    path_ptr_pos: u64,
+    title_ptr_pos: u64,
+    cluster_ptr_pos: u64,
+    mime_list_pos: u64,
+    main_page: u32,
+    checksum_pos: u64,
+}
+
+impl Header {
+    fn parse(bytes: &[u8; HEADER_SIZE]) -> Header {
+        let u16_at = |pos: usize| u16::from_le_bytes(bytes[pos..pos + 2].try_into().unwrap());
+        let u32_at = |pos: usize| u32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap());
+        let u64_at = |pos: usize| u64::from_le_bytes(bytes[pos..pos + 8].try_into().unwrap());
+        Header {
+            minor: u16_at(6),
+            uuid: bytes[8..24].try_into().unwrap(),
+            entry_count: u32_at(24),
+            cluster_count: u32_at(28),
+            path_ptr_pos: u64_at(32),
+            title_ptr_pos: u64_at(40),
+            cluster_ptr_pos: u64_at(48),
+            mime_list_pos: u64_at(56),
+            main_page: u32_at(64),
+            checksum_pos: u64_at(72),
+        }
+    }
+}
+
+/// One of the files the archive is read from.
+pub(crate) struct PartFile {
+    pub(crate) file: File,
+    pub(crate) path: Option<PathBuf>,
+    /// Offset of the part in `file`.
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+}
+
+/// The concatenation of all parts, read at arbitrary positions.
+struct Source {
+    parts: Vec<PartFile>,
+    /// Position of each part in the archive.
+    starts: Vec<u64>,
+    size: u64,
+}
+
+impl Source {
+    fn new(parts: Vec<PartFile>) -> Source {
+        let mut starts = Vec::with_capacity(parts.len());
+        let mut size = 0;
+        for part in &parts {
+            starts.push(size);
+            size += part.size;
+        }
+        Source {
+            parts,
+            starts,
+            size,
+        }
+    }
+
+    fn part_at(&self, pos: u64) -> usize {
+        self.starts.partition_point(|&start| start <= pos) - 1
+    }
+
+    fn read_at(&self, mut pos: u64, mut buf: &mut [u8]) -> io::Result<()> {
+        if pos + buf.len() as u64 > self.size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("read past the end of the archive at offset {}", pos),
+            ));
+        }
+        while !buf.is_empty() {
+            let index = self.part_at(pos);
+            let part = &self.parts[index];
+            let in_part = pos - self.starts[index];
+            let len = buf.len().min((part.size - in_part) as usize);
+            let (head, tail) = buf.split_at_mut(len);
+            read_file_at(&part.file, head, part.offset + in_part)?;
+            buf = tail;
+            pos += len as u64;
+        }
+        Ok(())
+    }
+
+    fn read_vec(&self, pos: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        self.read_at(pos, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// The file and offset in it holding `range`, if it is not split across parts.
+    fn locate(&self, range: Range<u64>) -> Option<(Option<&Path>, u64)> {
+        let index = self.part_at(range.start);
+        let part = &self.parts[index];
+        let in_part = range.start - self.starts[index];
+        match range.end - self.starts[index] <= part.size {
+            true => Some((part.path.as_deref(), part.offset + in_part)),
+            false => None,
+        }
+    }
+}
+
+/// Reads the archive from `pos` to its end, as much as the reader asks for.
+struct SourceReader<'a> {
+    source: &'a Source,
+    pos: u64,
+}
+
+impl Read for SourceReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = (buf.len() as u64).min(self.source.size.saturating_sub(self.pos)) as usize;
+        self.source.read_at(self.pos, &mut buf[..len])?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl SourceReader<'_> {
+    fn byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0];
+        self.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn skip(&mut self, len: u64) {
+        self.pos += len;
+    }
+
+    /// Skips a multibyte integer of the xz index.
+    fn skip_xz_integer(&mut self) -> io::Result<()> {
+        for _ in 0..9 {
+            if self.byte()? & 0x80 == 0 {
+                return Ok(());
+            }
+        }
+        Err(xz_framing("integer too long"))
+    }
+}
+
+fn xz_framing(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("xz framing: {}", what))
+}
+
+/// Length of the xz stream starting at `input`, found from the block and LZMA2
+/// chunk headers without decompressing it, since lzma-rs rejects any data
+/// after the stream.
+fn xz_stream_len(mut input: SourceReader) -> io::Result<u64> {
+    let start = input.pos;
+    let mut header = [0; 12];
+    input.read_exact(&mut header)?;
+    if header[..6] != [0xfd, b'7', b'z', b'X', b'Z', 0] {
+        return Err(xz_framing("bad stream header"));
+    }
+    let check_size = match header[7] & 0x0f {
+        0 => 0,
+        check => 4 << ((check - 1) / 3),
+    };
+    loop {
+        let block_start = input.pos;
+        let header_size = input.byte()?;
+        if header_size == 0 {
+            break;
+        }
+        input.skip(u64::from(header_size) * 4 + 3);
+        // The block data is LZMA2, the last filter of any chain.
+        loop {
+            match input.byte()? {
+                0 => break,
+                1 | 2 => {
+                    let size = u16::from_be_bytes([input.byte()?, input.byte()?]);
+                    input.skip(u64::from(size) + 1);
+                }
+                control @ 0x80.. => {
+                    input.skip(2);
+                    let size = u16::from_be_bytes([input.byte()?, input.byte()?]);
+                    let props = u64::from(control >= 0xc0);
+                    input.skip(props + u64::from(size) + 1);
+                }
+                _ => return Err(xz_framing("bad LZMA2 chunk")),
+            }
+        }
+        let padding = (4 - (input.pos - block_start) % 4) % 4;
+        input.skip(padding + check_size);
+    }
+    // The index indicator was read, then its record count and records.
+    let index_start = input.pos - 1;
+    let mut records = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = input.byte()?;
+        records |= u64::from(byte & 0x7f) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        if shift >= 63 {
+            return Err(xz_framing("integer too long"));
+        }
+    }
+    for _ in 0..records * 2 {
+        input.skip_xz_integer()?;
+    }
+    let padding = (4 - (input.pos - index_start) % 4) % 4;
+    // Index padding and CRC32, then the stream footer.
+    input.skip(padding + 4 + 12);
+    if input.pos > input.source.size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(input.pos - start)
+}
+
+#[cfg(unix)]
+fn read_file_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_file_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// What a dirent points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Target {
+    Redirect(u32),
+    Blob {
+        cluster: u32,
+        blob: u32,
+    },
+    /// Link targets and deleted entries have no content.
+    None,
+}
+
+/// A directory entry, one per entry of the archive.
+#[derive(Debug)]
+pub(crate) struct Dirent {
+    pub(crate) mimetype: u16,
+    pub(crate) namespace: u8,
+    pub(crate) target: Target,
+    pub(crate) path: String,
+    title: String,
+}
+
+impl Dirent {
+    pub(crate) fn is_redirect(&self) -> bool {
+        matches!(self.target, Target::Redirect(_))
+    }
+
+    /// Entries without a title use their path as title.
+    pub(crate) fn title(&self) -> &str {
+        match self.title.is_empty() {
+            true => &self.path,
+            false => &self.title,
+        }
+    }
+
+    /// The path with its namespace, as used by archives with the old namespace scheme.
+    pub(crate) fn long_path(&self) -> String {
+        format!("{}/{}", self.namespace as char, self.path)
+    }
+
+    fn path_key(&self) -> (u8, &[u8]) {
+        (self.namespace, self.path.as_bytes())
+    }
+
+    fn title_key(&self) -> (u8, &[u8]) {
+        (self.namespace, self.title().as_bytes())
+    }
+}
+
+/// Offsets of the blobs of a cluster, relative to the start of its offset list.
+pub(crate) enum Cluster {
+    /// The whole decompressed cluster, offset list included.
+    Compressed { data: Arc<[u8]>, offsets: Vec<u64> },
+    /// Blobs are read from the archive on demand, starting from `start`.
+    Uncompressed { start: u64, offsets: Vec<u64> },
+}
+
+impl Cluster {
+    fn offsets(&self) -> &[u64] {
+        match self {
+            Cluster::Compressed { offsets, .. } | Cluster::Uncompressed { offsets, .. } => offsets,
+        }
+    }
+
+    /// Bytes this cluster holds in memory.
+    fn memory_size(&self) -> u64 {
+        let offsets = self.offsets().len() as u64 * 8;
+        match self {
+            Cluster::Compressed { data, .. } => data.len() as u64 + offsets,
+            Cluster::Uncompressed { .. } => offsets,
+        }
+    }
+
+    fn blob_range(&self, blob: u32) -> Option<Range<u64>> {
+        let offsets = self.offsets();
+        let blob = blob as usize;
+        match blob + 1 < offsets.len() {
+            true => Some(offsets[blob]..offsets[blob + 1]),
+            false => None,
+        }
+    }
+}
+
+/// Bounded cache dropping the oldest values first, once the total size of the values
+/// is above `max_size`.
+struct Cache<K, V> {
+    max_size: u64,
+    size: u64,
+    values: HashMap<K, (V, u64)>,
+    order: VecDeque<K>,
+}
+
+impl<K: Copy + Eq + Hash, V: Clone> Cache<K, V> {
+    fn new(max_size: u64) -> Cache<K, V> {
+        Cache {
+            max_size,
+            size: 0,
+            values: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: K) -> Option<V> {
+        self.values.get(&key).map(|(value, _)| value.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V, size: u64) {
+        match self.values.insert(key, (value, size)) {
+            Some((_, old_size)) => self.size -= old_size,
+            None => self.order.push_back(key),
+        }
+        self.size += size;
+        self.evict();
+    }
+
+    fn set_max_size(&mut self, max_size: u64) {
+        self.max_size = max_size;
+        self.evict();
+    }
+
+    fn retain(&mut self, keep: impl Fn(&K) -> bool) {
+        self.order.retain(|key| keep(key));
+        self.values.retain(|key, _| keep(key));
+        self.size = self.values.values().map(|(_, size)| size).sum();
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.max_size {
+            match self.order.pop_front() {
+                Some(key) => {
+                    if let Some((_, size)) = self.values.remove(&key) {
+                        self.size -= size;
+                    }
+                }
+                None => break,
+            };
+        }
+    }
+}
+
+/// Decompressed clusters of all the open archives, keyed by reader and cluster index.
+/// Like libzim's, this cache is shared by every archive and bounded in bytes.
+static CLUSTERS: LazyLock<Mutex<ClusterCache>> =
+    LazyLock::new(|| Mutex::new(Cache::new(DEFAULT_CLUSTER_CACHE_SIZE)));
+
+type ClusterCache = Cache<(u64, u32), Arc<Cluster>>;
+
+static NEXT_READER_ID: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn cluster_cache_max_size() -> u64 {
+    CLUSTERS.lock().unwrap().max_size
+}
+
+pub(crate) fn cluster_cache_current_size() -> u64 {
+    CLUSTERS.lock().unwrap().size
+}
+
+pub(crate) fn set_cluster_cache_max_size(max_size: u64) {
+    CLUSTERS.lock().unwrap().set_max_size(max_size)
+}
+
+/// A list of `u32` entry indexes stored uncompressed in the archive.
+#[derive(Clone, Copy)]
+struct Listing {
+    pos: u64,
+    len: u32,
+}
+
+/// Reads an opened archive. Every lookup goes through the dirent and cluster caches.
+pub(crate) struct Reader {
+    source: Source,
+    filename: String,
+    header: Header,
+    mimetypes: Vec<String>,
+    cluster_offsets: Vec<u64>,
+    /// Sorted start of every cluster and of most pointer lists, and the end of the archive,
+    /// so that an uncompressed cluster ends before the next boundary after its start.
+    boundaries: Vec<u64>,
+    /// Key of the clusters of this archive in `CLUSTERS`.
+    id: u64,
+    dirents: Mutex<Cache<u32, Arc<Dirent>>>,
+    lookup_size: u64,
+    /// Evenly spread `(index, namespace, path)` narrowing path lookups, `lookup_size`
+    /// of them at most.
+    lookup: OnceLock<Vec<(u32, u8, String)>>,
+    titles: Listing,
+    articles: Option<Listing>,
+    efficient_order: OnceLock<Vec<u32>>,
+}
+
+impl Reader {
+    /// Open the archive stored in `parts`, `name` describing it in errors. Path
+    /// lookups are narrowed by a table of `lookup_size` dirents read on first use.
+    pub(crate) fn open(
+        parts: Vec<PartFile>,
+        filename: String,
+        name: &str,
+        lookup_size: u64,
+    ) -> Result<Reader, Error> {
+        let source = Source::new(parts);
+        let mut bytes = [0u8; HEADER_SIZE];
+        let read = source.read_at(0, &mut bytes);
+        let magic = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let major = u16::from_le_bytes(bytes[4..6].try_into().unwrap());
+        if read.is_err() || magic != ZIM_MAGIC || (major != 5 && major != 6) {
+            return Err(diagnose_header(name, read, bytes[..8].try_into().unwrap()));
+        }
+        let header = Header::parse(&bytes);
+        let invalid = |what: &str| Error::ZimFileFormat(format!("{}: {}", name, what));
+
+        let entries_size = header.entry_count as u64 * 8;
+        let clusters_size = header.cluster_count as u64 * 8;
+        let in_archive =
+            |pos: u64, len: u64| pos.checked_add(len).is_some_and(|end| end <= source.size);
+        if !in_archive(header.path_ptr_pos, entries_size)
+            || !in_archive(header.cluster_ptr_pos, clusters_size)
+            || !in_archive(header.mime_list_pos, 0)
+        {
+            return Err(invalid("pointer list out of the archive"));
+        }
+        if header.checksum_pos != 0 && !in_archive(header.checksum_pos, 16) {
+            return Err(invalid("checksum out of the archive"));
+        }
+
+        let cluster_offsets = source
+            .read_vec(header.cluster_ptr_pos, clusters_size as usize)?
+            .chunks_exact(8)
+            .map(|offset| u64::from_le_bytes(offset.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        if cluster_offsets.iter().any(|&offset| offset >= source.size) {
+            return Err(invalid("cluster out of the archive"));
+        }
+        let mut boundaries = cluster_offsets.clone();
+        // The header title list is left out: with the new namespace scheme, it points
+        // into the uncompressed cluster holding `X/listing/titleOrdered/v0`.
+        boundaries.extend([
+            header.path_ptr_pos,
+            header.cluster_ptr_pos,
+            header.mime_list_pos,
+            source.size,
+        ]);
+        if header.checksum_pos != 0 {
+            boundaries.push(header.checksum_pos);
+        }
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mime_end = boundaries[boundaries.partition_point(|&b| b <= header.mime_list_pos)..]
+            .first()
+            .copied()
+            .unwrap_or(source.size);
+        let mime_list = source.read_vec(
+            header.mime_list_pos,
+            (mime_end - header.mime_list_pos) as usize,
+        )?;
+        let mimetypes = mime_list
+            .split(|&b| b == 0)
+            .take_while(|mimetype| !mimetype.is_empty())
+            .map(|mimetype| String::from_utf8_lossy(mimetype).into_owned())
+            .collect();
+
+        let titles = Listing {
+            pos: header.title_ptr_pos,
+            len: header.entry_count,
+        };
+        let mut reader = Reader {
+            source,
+            filename,
+            header,
+            mimetypes,
+            cluster_offsets,
+            boundaries,
+            id: NEXT_READER_ID.fetch_add(1, Ordering::Relaxed),
+            dirents: Mutex::new(Cache::new(DEFAULT_DIRENT_CACHE_SIZE)),
+            lookup_size,
+            lookup: OnceLock::new(),
+            titles,
+            articles: None,
+            efficient_order: OnceLock::new(),
+        };
+        if reader.has_new_namespace_scheme() {
+            if let Some(titles) = reader.listing("listing/titleOrdered/v0")? {
+                reader.titles = titles;
+            }
+            reader.articles = reader.listing("listing/titleOrdered/v1")?;
+        }
+        if !in_archive_listing(&reader.source, reader.titles) {
+            return Err(invalid("title pointer list out of the archive"));
+        }
+        Ok(reader)
+    }
+
+    /// The `X/<path>` listing, if it exists and is stored uncompressed as libzim requires.
+    fn listing(&self, path: &str) -> Result<Option<Listing>, Error> {
+        let Some(index) = self.find(b'X', path)? else {
+            return Ok(None);
+        };
+        let dirent = self.dirent(index)?;
+        let Target::Blob { cluster, blob } = dirent.target else {
+            return Ok(None);
+        };
+        Ok(self.blob_location(cluster, blob)?.map(|range| Listing {
+            pos: range.start,
+            len: ((range.end - range.start) / 4) as u32,
+        }))
+    }
+
+    pub(crate) fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.source.size
+    }
+
+    pub(crate) fn uuid(&self) -> [u8; 16] {
+        self.header.uuid
+    }
+
+    pub(crate) fn entry_count(&self) -> u32 {
+        self.header.entry_count
+    }
+
+    pub(crate) fn main_page(&self) -> Option<u32> {
+        match self.header.main_page {
+            NO_MAIN_PAGE => None,
+            index => Some(index),
+        }
+    }
+
+    pub(crate) fn has_new_namespace_scheme(&self) -> bool {
+        self.header.minor >= 1
+    }
+
+    pub(crate) fn mimetype(&self, dirent: &Dirent) -> Result<String, Error> {
+        match self.mimetypes.get(dirent.mimetype as usize) {
+            Some(mimetype) => Ok(mimetype.clone()),
+            None => Err(Error::ZimFileFormat(format!(
+                "entry {} has no mimetype",
+                dirent.path
+            ))),
+        }
+    }
+
+    pub(crate) fn part_count(&self) -> usize {
+        self.source.parts.len()
+    }
+
+    // Dirents
+
+    pub(crate) fn dirent(&self, index: u32) -> Result<Arc<Dirent>, Error> {
+        if let Some(dirent) = self.dirents.lock().unwrap().get(index) {
+            return Ok(dirent);
+        }
+        let dirent = Arc::new(self.read_dirent(index)?);
+        self.dirents
+            .lock()
+            .unwrap()
+            .insert(index, dirent.clone(), 1);
+        Ok(dirent)
+    }
+
+    fn read_dirent(&self, index: u32) -> Result<Dirent, Error> {
+        if index >= self.header.entry_count {
+            return Err(Error::EntryNotFound(format!("no entry at index {}", index)));
+        }
+        let mut ptr = [0u8; 8];
+        self.source
+            .read_at(self.header.path_ptr_pos + index as u64 * 8, &mut ptr)?;
+        let pos = u64::from_le_bytes(ptr);
+        let invalid =
+            || Error::ZimFileFormat(format!("invalid dirent {} at offset {}", index, pos));
+
+        let mut len = DIRENT_READ_SIZE;
+        loop {
+            let len_read = len.min(self.source.size.saturating_sub(pos) as usize);
+            let bytes = self.source.read_vec(pos, len_read).map_err(|_| invalid())?;
+            if bytes.len() < 8 {
+                return Err(invalid());
+            }
+            let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+            let mimetype = u16::from_le_bytes([bytes[0], bytes[1]]);
+            let namespace = bytes[3];
+            let (target, fixed) = match mimetype {
+                MIMETYPE_REDIRECT if bytes.len() >= 12 => (Target::Redirect(u32_at(8)), 12),
+                MIMETYPE_LINK_TARGET | MIMETYPE_DELETED => (Target::None, 8),
+                MIMETYPE_REDIRECT => return Err(invalid()),
+                _ if bytes.len() >= 16 => (
+                    Target::Blob {
+                        cluster: u32_at(8),
+                        blob: u32_at(12),
+                    },
+                    16,
+                ),
+                _ => return Err(invalid()),
+            };
+            let mut strings = bytes[fixed..].splitn(3, |&b| b == 0);
+            if let (Some(path), Some(title), Some(_)) =
+                (strings.next(), strings.next(), strings.next())
+            {
+                return Ok(Dirent {
+                    mimetype,
+                    namespace,
+                    target,
+                    path: String::from_utf8_lossy(path).into_owned(),
+                    title: String::from_utf8_lossy(title).into_owned(),
+                });
+            }
+            if len_read < len {
+                return Err(invalid());
+            }
+            len *= 2;
+        }
+    }
+
+    pub(crate) fn dirent_cache_max_size(&self) -> u64 {
+        self.dirents.lock().unwrap().max_size
+    }
+
+    pub(crate) fn dirent_cache_current_size(&self) -> u64 {
+        self.dirents.lock().unwrap().size
+    }
+
+    pub(crate) fn set_dirent_cache_max_size(&self, max_size: u64) {
+        self.dirents.lock().unwrap().set_max_size(max_size)
+    }
+
+    pub(crate) fn lookup_size(&self) -> u64 {
+        self.lookup_size
+    }
+
+    fn lookup_table(&self) -> Result<&[(u32, u8, String)], Error> {
+        if let Some(table) = self.lookup.get() {
+            return Ok(table);
+        }
+        let count = self.header.entry_count as u64;
+        let size = self.lookup_size().min(count);
+        let mut table = Vec::with_capacity(size as usize);
+        if size >= 2 {
+            for i in 0..size {
+                let index = (i * (count - 1) / (size - 1)) as u32;
+                let dirent = self.read_dirent(index)?;
+                table.push((index, dirent.namespace, dirent.path));
+            }
+        }
+        Ok(self.lookup.get_or_init(|| table))
+    }
+
+    /// The first position of `range` for which `is_before` is false,
+    /// `is_before` being true for a prefix of the range.
+    fn partition_point<F>(&self, range: Range<u32>, mut is_before: F) -> Result<u32, Error>
+    where
+        F: FnMut(u32) -> Result<bool, Error>,
+    {
+        let (mut low, mut high) = (range.start, range.end);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match is_before(mid)? {
+                true => low = mid + 1,
+                false => high = mid,
+            }
+        }
+        Ok(low)
+    }
+
+    /// Index of the first entry whose path is not before `path` in `namespace`.
+    pub(crate) fn path_lower_bound(&self, namespace: u8, path: &str) -> Result<u32, Error> {
+        let key = (namespace, path.as_bytes());
+        let table = self.lookup_table()?;
+        let split = table.partition_point(|(_, ns, path)| (*ns, path.as_bytes()) < key);
+        let low = match split {
+            0 => 0,
+            split => table[split - 1].0 + 1,
+        };
+        let high = match table.get(split) {
+            Some((index, _, _)) => *index,
+            None => self.header.entry_count,
+        };
+        self.partition_point(low..high, |index| Ok(self.dirent(index)?.path_key() < key))
+    }
+
+    /// Indexes of the entries of `namespace` whose path starts with `prefix`.
+    pub(crate) fn path_prefix_range(
+        &self,
+        namespace: u8,
+        prefix: &str,
+    ) -> Result<Range<u32>, Error> {
+        let start = self.path_lower_bound(namespace, prefix)?;
+        let end = self.partition_point(start..self.header.entry_count, |index| {
+            let dirent = self.dirent(index)?;
+            Ok(dirent.namespace == namespace && dirent.path.starts_with(prefix))
+        })?;
+        Ok(start..end)
+    }
+
+    pub(crate) fn namespace_range(&self, namespace: u8) -> Result<Range<u32>, Error> {
+        self.path_prefix_range(namespace, "")
+    }
+
+    pub(crate) fn find(&self, namespace: u8, path: &str) -> Result<Option<u32>, Error> {
+        let index = self.path_lower_bound(namespace, path)?;
+        if index < self.header.entry_count {
+            let dirent = self.dirent(index)?;
+            if dirent.namespace == namespace && dirent.path == path {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Indexes of the entries reachable without a namespace: `C` with the new
+    /// namespace scheme, all of them otherwise.
+    pub(crate) fn user_range(&self) -> Result<Range<u32>, Error> {
+        match self.has_new_namespace_scheme() {
+            true => self.namespace_range(b'C'),
+            false => Ok(0..self.header.entry_count),
+        }
+    }
+
+    // Title listings
+
+    fn listing_entry(&self, listing: Listing, pos: u32) -> Result<u32, Error> {
+        if pos >= listing.len {
+            return Err(Error::EntryNotFound(format!(
+                "no entry at title index {}",
+                pos
+            )));
+        }
+        let mut index = [0u8; 4];
+        self.source
+            .read_at(listing.pos + pos as u64 * 4, &mut index)?;
+        Ok(u32::from_le_bytes(index))
+    }
+
+    pub(crate) fn title_count(&self) -> u32 {
+        self.titles.len
+    }
+
+    /// Index of the entry at `pos` in title order.
+    pub(crate) fn title_entry(&self, pos: u32) -> Result<u32, Error> {
+        self.listing_entry(self.titles, pos)
+    }
+
+    /// The first position in title order whose entry is not before `title` in `namespace`.
+    fn title_lower_bound(&self, namespace: u8, title: &str) -> Result<u32, Error> {
+        let key = (namespace, title.as_bytes());
+        self.partition_point(0..self.titles.len, |pos| {
+            Ok(self.dirent(self.title_entry(pos)?)?.title_key() < key)
+        })
+    }
+
+    /// Positions in title order of the entries of `namespace` whose title starts with `prefix`.
+    pub(crate) fn title_prefix_range(
+        &self,
+        namespace: u8,
+        prefix: &str,
+    ) -> Result<Range<u32>, Error> {
+        let start = self.title_lower_bound(namespace, prefix)?;
+        let end = self.partition_point(start..self.titles.len, |pos| {
+            let dirent = self.dirent(self.title_entry(pos)?)?;
+            Ok(dirent.namespace == namespace && dirent.title().starts_with(prefix))
+        })?;
+        Ok(start..end)
+    }
+
+    /// Index of the entry of `namespace` titled exactly `title`, which is the first
+    /// one not before it in title order.
+    pub(crate) fn find_by_title(&self, namespace: u8, title: &str) -> Result<Option<u32>, Error> {
+        let pos = self.title_lower_bound(namespace, title)?;
+        if pos < self.titles.len {
+            let index = self.title_entry(pos)?;
+            if self.dirent(index)?.title_key() == (namespace, title.as_bytes()) {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Number of front articles, if the archive lists them.
+    pub(crate) fn article_count(&self) -> Option<u32> {
+        self.articles.map(|articles| articles.len)
+    }
+
+    /// Index of the front article at `pos` in title order.
+    pub(crate) fn article_entry(&self, pos: u32) -> Result<Option<u32>, Error> {
+        match self.articles {
+            Some(articles) => self.listing_entry(articles, pos).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Indexes of the entries of [`Reader::user_range`] sorted by cluster, so that
+    /// reading them in this order decompresses each cluster once.
+    pub(crate) fn efficient_order(&self) -> Result<&[u32], Error> {
+        if let Some(order) = self.efficient_order.get() {
+            return Ok(order);
+        }
+        let mut keyed = self
+            .user_range()?
+            .map(|index| {
+                let key = match self.read_dirent(index)?.target {
+                    Target::Blob { cluster, blob } => (cluster as u64 + 1, blob),
+                    _ => (0, 0),
+                };
+                Ok((key, index))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        keyed.sort_by_key(|(key, _)| *key);
+        let order = keyed.into_iter().map(|(_, index)| index).collect();
+        Ok(self.efficient_order.get_or_init(|| order))
+    }
+
+    // Clusters
+
+    pub(crate) fn cluster(&self, index: u32) -> Result<Arc<Cluster>, Error> {
+        if let Some(cluster) = CLUSTERS.lock().unwrap().get((self.id, index)) {
+            return Ok(cluster);
+        }
+        let cluster = Arc::new(self.read_cluster(index)?);
+        let size = cluster.memory_size();
+        CLUSTERS
+            .lock()
+            .unwrap()
+            .insert((self.id, index), cluster.clone(), size);
+        Ok(cluster)
+    }
+
+    fn read_cluster(&self, index: u32) -> Result<Cluster, Error> {
+        let invalid = |what: &str| Error::ZimFileFormat(format!("cluster {}: {}", index, what));
+        let start = match self.cluster_offsets.get(index as usize) {
+            Some(&start) => start,
+            None => return Err(invalid("no such cluster")),
+        };
+        let mut info = [0u8; 1];
+        self.source.read_at(start, &mut info)?;
+        let offset_size = match info[0] & 0x10 {
+            0 => 4,
+            _ => 8,
+        };
+        match info[0] & 0x0f {
+            0 | 1 => {
+                let end = self.boundaries[self.boundaries.partition_point(|&b| b <= start)..]
+                    .first()
+                    .copied()
+                    .unwrap_or(self.source.size);
+                let first = self.source.read_vec(start + 1, offset_size)?;
+                let count = parse_offsets(&first, offset_size)[0] as usize / offset_size;
+                if count == 0 || (start + 1).saturating_add((count * offset_size) as u64) > end {
+                    return Err(invalid("offset list out of the cluster"));
+                }
+                let bytes = self.source.read_vec(start + 1, count * offset_size)?;
+                let offsets = parse_offsets(&bytes, offset_size);
+                check_offsets(&offsets, end - start - 1)
+                    .ok_or_else(|| invalid("invalid offsets"))?;
+                Ok(Cluster::Uncompressed {
+                    start: start + 1,
+                    offsets,
+                })
+            }
+            compression @ (4 | 5) => {
+                // Only the compressed stream is read, however far the next cluster is.
+                let mut compressed = SourceReader {
+                    source: &self.source,
+                    pos: start + 1,
+                };
+                let mut data = Vec::new();
+                let decompressed = match compression {
+                    4 => {
+                        let len = xz_stream_len(compressed).map_err(|err| match err.kind() {
+                            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                                invalid(&err.to_string())
+                            }
+                            _ => err.into(),
+                        })?;
+                        let len = usize::try_from(len)
+                            .map_err(|_| invalid("compressed stream too large"))?;
+                        let stream = self.source.read_vec(start + 1, len)?;
+                        lzma_rs::xz_decompress(&mut stream.as_slice(), &mut data)
+                            .map_err(|err| err.to_string())
+                    }
+                    _ => ruzstd::decoding::StreamingDecoder::new(io::BufReader::with_capacity(
+                        CLUSTER_READ_SIZE,
+                        &mut compressed,
+                    ))
+                    .map_err(|err| err.to_string())
+                    .and_then(|mut decoder| {
+                        decoder
+                            .read_to_end(&mut data)
+                            .map_err(|err| err.to_string())
+                    })
+                    .map(|_| ()),
+                };
+                decompressed.map_err(|err| invalid(&err))?;
+                let first = data
+                    .get(..offset_size)
+                    .ok_or_else(|| invalid("empty cluster"))?;
+                let count = parse_offsets(first, offset_size)[0] as usize / offset_size;
+                let bytes = data
+                    .get(..count * offset_size)
+                    .filter(|_| count > 0)
+                    .ok_or_else(|| invalid("offset list out of the cluster"))?;
+                let offsets = parse_offsets(bytes, offset_size);
+                check_offsets(&offsets, data.len() as u64)
+                    .ok_or_else(|| invalid("invalid offsets"))?;
+                Ok(Cluster::Compressed {
+                    data: data.into(),
+                    offsets,
+                })
+            }
+            compression => Err(invalid(&format!("unsupported compression {}", compression))),
+        }
+    }
+
+    // Blobs
+
+    fn blob_range(&self, cluster: &Cluster, index: u32, blob: u32) -> Result<Range<u64>, Error> {
+        cluster
+            .blob_range(blob)
+            .ok_or_else(|| Error::ZimFileFormat(format!("cluster {} has no blob {}", index, blob)))
+    }
+
+    pub(crate) fn blob_size(&self, cluster: u32, blob: u32) -> Result<u64, Error> {
+        let range = self.blob_range(&*self.cluster(cluster)?, cluster, blob)?;
+        Ok(range.end - range.start)
+    }
+
+    /// `size` bytes of the blob from `offset`, truncated at the end of the blob.
+    pub(crate) fn blob(
+        &self,
+        cluster: u32,
+        blob: u32,
+        offset: u64,
+        size: u64,
+    ) -> Result<Blob, Error> {
+        let index = cluster;
+        let cluster = self.cluster(index)?;
+        let range = self.blob_range(&cluster, index, blob)?;
+        let start = range.start.saturating_add(offset).min(range.end);
+        let end = start.saturating_add(size).min(range.end);
+        match &*cluster {
+            Cluster::Compressed { data, .. } => Ok(Blob::from_shared(
+                data.clone(),
+                start as usize..end as usize,
+            )),
+            Cluster::Uncompressed { start: base, .. } => {
+                let data = self.source.read_vec(base + start, (end - start) as usize)?;
+                let len = data.len();
+                Ok(Blob::from_shared(data.into(), 0..len))
+            }
+        }
+    }
+
+    /// Where the blob is stored in the archive, if its cluster is not compressed.
+    fn blob_location(&self, cluster: u32, blob: u32) -> Result<Option<Range<u64>>, Error> {
+        let index = cluster;
+        let cluster = self.cluster(index)?;
+        let range = self.blob_range(&cluster, index, blob)?;
+        match &*cluster {
+            Cluster::Compressed { .. } => Ok(None),
+            Cluster::Uncompressed { start, .. } => Ok(Some(start + range.start..start + range.end)),
+        }
+    }
+
+    /// The file and offset holding the blob, if it is neither compressed nor split across parts.
+    pub(crate) fn direct_access(&self, cluster: u32, blob: u32) -> Option<(PathBuf, u64)> {
+        let range = self.blob_location(cluster, blob).ok()??;
+        let (path, offset) = self.source.locate(range)?;
+        Some((path?.to_path_buf(), offset))
+    }
+
+    // Checksum
+
+    pub(crate) fn has_checksum(&self) -> bool {
+        self.header.checksum_pos != 0
+    }
+
+    pub(crate) fn checksum(&self) -> Result<Option<[u8; 16]>, Error> {
+        if !self.has_checksum() {
+            return Ok(None);
+        }
+        let mut checksum = [0u8; 16];
+        self.source
+            .read_at(self.header.checksum_pos, &mut checksum)?;
+        Ok(Some(checksum))
+    }
+
+    /// Whether the md5 of everything before the checksum matches it.
+    pub(crate) fn verify_checksum(&self) -> Result<bool, Error> {
+        use md5::{Digest, Md5};
+
+        let Some(expected) = self.checksum()? else {
+            return Ok(false);
+        };
+        let mut md5 = Md5::new();
+        let mut buf = vec![0u8; crate::item::DEFAULT_CHUNK_SIZE as usize];
+        let mut pos = 0;
+        while pos < self.header.checksum_pos {
+            let len = buf.len().min((self.header.checksum_pos - pos) as usize);
+            self.source.read_at(pos, &mut buf[..len])?;
+            md5.update(&buf[..len]);
+            pos += len as u64;
+        }
+        Ok(md5.finalize()[..] == expected)
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        let id = self.id;
+        CLUSTERS.lock().unwrap().retain(|&(reader, _)| reader != id);
+    }
+}
+
+fn in_archive_listing(source: &Source, listing: Listing) -> bool {
+    listing
+        .pos
+        .checked_add(listing.len as u64 * 4)
+        .is_some_and(|end| end <= source.size)
+}
+
+fn parse_offsets(bytes: &[u8], offset_size: usize) -> Vec<u64> {
+    bytes
+        .chunks_exact(offset_size)
+        .map(|offset| match offset_size {
+            4 => u32::from_le_bytes(offset.try_into().unwrap()) as u64,
+            _ => u64::from_le_bytes(offset.try_into().unwrap()),
+        })
+        .collect()
+}
+
+/// Offsets must be increasing and stay within the `len` bytes of the cluster.
+fn check_offsets(offsets: &[u64], len: u64) -> Option<()> {
+    let sorted = offsets.windows(2).all(|pair| pair[0] <= pair[1]);
+    match sorted && offsets.last().is_some_and(|&last| last <= len) {
+        true => Some(()),
+        false => None,
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    blob::Blob,
+    error::Error,
+    item::{DirectAccess, Item},
+    native::format::Target,
+};
+
+impl Item {
+    pub fn get_title(&self) -> String {
+        self.dirent().title().to_string()
+    }
+
+    pub fn get_path(&self) -> String {
+        match self.archive().has_new_namespace_scheme() {
+            true => self.dirent().path.clone(),
+            false => self.dirent().long_path(),
+        }
+    }
+
+    pub fn get_mimetype(&self) -> Result<String, Error> {
+        self.archive().reader().mimetype(self.dirent())
+    }
+
+    pub fn get_data(&self) -> Result<Blob, Error> {
+        self.get_data_offset(0, u64::MAX)
+    }
+
+    /// `size` bytes from `offset`, truncated at the end of the item.
+    pub fn get_data_offset(&self, offset: u64, size: u64) -> Result<Blob, Error> {
+        match self.dirent().target {
+            Target::Blob { cluster, blob } => {
+                self.archive().reader().blob(cluster, blob, offset, size)
+            }
+            _ => Ok(Blob::from_shared(Vec::new().into(), 0..0)),
+        }
+    }
+
+    pub fn get_size(&self) -> u64 {
+        match self.dirent().target {
+            Target::Blob { cluster, blob } => self
+                .archive()
+                .reader()
+                .blob_size(cluster, blob)
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// The location of the item's bytes on disk, for serving them without copying
+    /// (`sendfile`, `mmap`, ...).
+    ///
+    /// `None` if the item is stored in a compressed cluster or split across parts,
+    /// or if the archive was not opened from a file path.
+    pub fn direct_access(&self) -> Option<DirectAccess> {
+        match self.dirent().target {
+            Target::Blob { cluster, blob } => {
+                let (path, offset) = self.archive().reader().direct_access(cluster, blob)?;
+                Some(DirectAccess { path, offset })
+            }
+            _ => None,
+        }
+    }
+}
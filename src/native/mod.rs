@@ -0,0 +1,8 @@
+//! Pure Rust implementation of [`Archive`](crate::archive::Archive),
+//! [`Entry`](crate::entry::Entry) and [`Item`](crate::item::Item), used instead of
+//! libzim with the `pure-rust` feature.
+
+pub mod archive;
+mod entry;
+pub(crate) mod format;
+mod item;
@@ -1,48 +1,211 @@
+use std::{
+    cell::{RefCell, RefMut},
+    collections::VecDeque,
+    rc::Rc,
+    sync::Arc,
+};
+
 use cxx::UniquePtr;
-use zim_sys::binding::ffi;
 
-use crate::{archive::Archive, entry::Entry};
+use crate::{
+    archive::Archive,
+    binding::ffi,
+    cursor::{SearchCursor, MAX_RESTRICTION_LEN},
+    cxx::{int_from_usize, string_from_ptr, usize_from_int},
+    entry::Entry,
+    error::Error,
+    uuid::Uuid,
+};
+
+/// libzim searchers a [`MultiSearcher`] keeps for the sets of archives searched last.
+const MAX_CACHED_SEARCHERS: usize = 8;
+
+/// Results read from libzim at a time while applying path prefix and mimetype
+/// restrictions.
+const RESTRICTED_PAGE_SIZE: usize = 64;
 
 pub struct Searcher {
     ptr: UniquePtr<ffi::Searcher>,
+    archives: Vec<Archive>,
+}
+
+/// A full-text searcher over a set of archives, searching all of them or only some
+/// at a time.
+///
+/// Results of all searched archives are ranked together by score, and each
+/// [`SearchResult`] tells which archive it comes from through
+/// [`SearchResult::zim_id`] and [`SearchResult::archive`].
+#[derive(Default)]
+pub struct MultiSearcher {
+    archives: Vec<(Uuid, Archive)>,
+    /// libzim searchers of the last sets of archives searched, keyed by sorted uuids
+    /// and least recently used first.
+    searchers: Vec<(Vec<Uuid>, Searcher)>,
+    verbose: bool,
 }
 
 pub struct Query {
     ptr: UniquePtr<ffi::Query>,
+    text: Arc<str>,
+    georange: Option<(f32, f32, f32)>,
+    path_prefix: Option<String>,
+    mimetype: Option<String>,
+    max_results: Option<usize>,
+}
+
+/// Builds a validated [`Query`].
+///
+/// There is no stemming language option: libzim stems queries in the language each
+/// archive was indexed in, and has no way to override it.
+///
+/// ```no_run
+/// use zim_rs::search::{DistanceUnit, QueryBuilder};
+///
+/// let query = QueryBuilder::new("museum")
+///     .georange(48.8566, 2.3522, 5.0, DistanceUnit::Kilometers)
+///     .mimetype("text/html")
+///     .max_results(100)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryBuilder {
+    text: Arc<str>,
+    georange: Option<(f32, f32, f32, DistanceUnit)>,
+    path_prefix: Option<String>,
+    mimetype: Option<String>,
+    max_results: Option<usize>,
+}
+
+/// Unit of the distance given to [`QueryBuilder::georange`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DistanceUnit {
+    #[default]
+    Meters,
+    Kilometers,
+    Miles,
 }
 
 pub struct Search {
-    ptr: UniquePtr<ffi::Search>,
+    /// Shared with the iterators over its results, which fetch results again to skip
+    /// ahead.
+    ptr: Rc<UniquePtr<ffi::Search>>,
+    archives: Vec<Archive>,
+    cursor: SearchCursor,
+    /// Results of libzim already checked against the restrictions of the query.
+    filtered: RefCell<Filtered>,
+}
+
+/// What is known of the results of libzim passing the path prefix and mimetype
+/// restrictions, kept so that each page does not check them again from the first one.
+#[derive(Default)]
+struct Filtered {
+    /// Offsets of the accepted results among the results of libzim.
+    accepted: Vec<usize>,
+    /// Number of results of libzim checked.
+    checked: usize,
+    /// Whether all the results of libzim were checked.
+    done: bool,
 }
 
 pub struct SearchResultSet {
     ptr: UniquePtr<ffi::SearchResultSet>,
+    search: Rc<UniquePtr<ffi::Search>>,
+    /// Offset of the first result of the set among the results of libzim.
+    start: usize,
+    archives: Vec<Archive>,
+    /// Whether each libzim result passes the restrictions of the query, `None` when
+    /// it has none.
+    keep: Option<VecDeque<bool>>,
 }
 
 pub struct SearchIterator {
     cur: UniquePtr<ffi::SearchIterator>,
     end: UniquePtr<ffi::SearchIterator>,
+    /// Number of results kept between `cur` and `end`.
     size: usize,
+    /// The search the results come from, `None` for an empty iterator.
+    search: Option<Rc<UniquePtr<ffi::Search>>>,
+    /// Offsets of `cur` and `end` among the results of libzim.
+    raw_start: usize,
+    raw_end: usize,
+    archives: Vec<Archive>,
+    /// Whether each libzim result between `cur` and `end` is kept, `None` when all are.
+    keep: Option<VecDeque<bool>>,
+}
+
+/// All results of a [`Search`], see [`Search::iter_all`].
+pub struct SearchPages<'a> {
+    search: &'a Search,
+    page_size: usize,
+    next_start: usize,
+    page: SearchIterator,
+    done: bool,
+    /// Results left before the limit of the query.
+    remaining: usize,
+}
+
+/// One hit of a full-text search, with what libzim knows about the match.
+pub struct SearchResult {
+    entry: Entry,
+    score: i32,
+    snippet: String,
+    word_count: Option<u32>,
+    size: Option<u64>,
+    file_index: usize,
+    zim_id: Uuid,
 }
 
 impl Searcher {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Searcher>) -> Result<Searcher, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Searcher { ptr }),
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Searcher>, archive: &Archive) -> Searcher {
+        Searcher {
+            ptr,
+            archives: vec![archive.clone()],
         }
     }
 
-    pub fn new(archive: &Archive) -> Result<Searcher, ()> {
-        Searcher::from_ptr(ffi::searcher_ctor(archive.inner_ref()))
+    pub fn new(archive: &Archive) -> Result<Searcher, Error> {
+        if !archive.has_fulltext_index() {
+            return Err(Error::SearchUnavailable(format!(
+                "{} has no full-text index",
+                archive.get_filename()
+            )));
+        }
+        let ptr = ffi::searcher_ctor(archive.inner_ref())?;
+        Ok(Searcher::from_ptr(ptr, archive))
     }
 
     pub fn add_archive(&mut self, archive: &Archive) {
-        ffi::searcher_addArchive(self.ptr.pin_mut(), archive.inner_ref())
+        ffi::searcher_addArchive(self.ptr.pin_mut(), archive.inner_ref());
+        self.archives.push(archive.clone());
     }
 
-    pub fn search(&mut self, query: &Query) -> Result<Search, ()> {
-        Search::from_ptr(ffi::searcher_search(self.ptr.pin_mut(), query.inner_ref()))
+    pub fn search(&mut self, query: &Query) -> Result<Search, Error> {
+        let cursor = SearchCursor::for_query(query);
+        let ptr = ffi::searcher_search(self.ptr.pin_mut(), query.inner_ref())?;
+        Ok(Search::from_ptr(ptr, &self.archives, cursor))
+    }
+
+    /// Rebuild the search `cursor` was taken from.
+    ///
+    /// The searcher must have been created on the same archives as the original
+    /// one, in any order. Results should then be fetched from [`SearchCursor::offset`].
+    pub fn resume(&mut self, cursor: &SearchCursor) -> Result<Search, Error> {
+        cursor.check_archives(&self.archives)?;
+        let mut builder = QueryBuilder::new(cursor.query());
+        if let Some((latitude, longitude, distance)) = cursor.georange() {
+            builder = builder.georange(latitude, longitude, distance, DistanceUnit::Meters);
+        }
+        if let Some(prefix) = cursor.path_prefix() {
+            builder = builder.path_prefix(prefix);
+        }
+        if let Some(mimetype) = cursor.mimetype() {
+            builder = builder.mimetype(mimetype);
+        }
+        if let Some(max_results) = cursor.max_results() {
+            builder = builder.max_results(max_results);
+        }
+        self.search(&builder.build()?)
     }
 
     pub fn set_verbose(&mut self, verbose: bool) {
@@ -50,11 +213,123 @@ impl Searcher {
     }
 }
 
+impl MultiSearcher {
+    pub fn new() -> MultiSearcher {
+        MultiSearcher::default()
+    }
+
+    /// Add an archive with a full-text index, returning the uuid it can be selected by.
+    pub fn add_archive(&mut self, archive: &Archive) -> Result<Uuid, Error> {
+        if !archive.has_fulltext_index() {
+            return Err(Error::SearchUnavailable(format!(
+                "{} has no full-text index",
+                archive.get_filename()
+            )));
+        }
+        let uuid = archive.get_uuid()?;
+        if self.archive(&uuid).is_some() {
+            return Err(Error::InvalidArgument(format!(
+                "archive {} was already added",
+                uuid
+            )));
+        }
+        self.archives.push((uuid, archive.clone()));
+        Ok(uuid)
+    }
+
+    /// The added archive with this uuid.
+    pub fn archive(&self, uuid: &Uuid) -> Option<&Archive> {
+        self.archives
+            .iter()
+            .find_map(|(id, archive)| (id == uuid).then_some(archive))
+    }
+
+    /// Added archives with their uuid, in the order they were added.
+    pub fn archives(&self) -> impl Iterator<Item = (Uuid, &Archive)> {
+        self.archives.iter().map(|(uuid, archive)| (*uuid, archive))
+    }
+
+    /// Search every added archive.
+    pub fn search(&mut self, query: &Query) -> Result<Search, Error> {
+        let uuids = self
+            .archives
+            .iter()
+            .map(|(uuid, _)| *uuid)
+            .collect::<Vec<_>>();
+        self.search_in(query, &uuids)
+    }
+
+    /// Search only the added archives with one of these uuids.
+    pub fn search_in(&mut self, query: &Query, uuids: &[Uuid]) -> Result<Search, Error> {
+        self.searcher_for(uuids)?.search(query)
+    }
+
+    /// Rebuild the search `cursor` was taken from, on the archives it was made for.
+    pub fn resume(&mut self, cursor: &SearchCursor) -> Result<Search, Error> {
+        self.searcher_for(cursor.archives())?.resume(cursor)
+    }
+
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+        for (_, searcher) in self.searchers.iter_mut() {
+            searcher.set_verbose(verbose);
+        }
+    }
+
+    fn searcher_for(&mut self, uuids: &[Uuid]) -> Result<&mut Searcher, Error> {
+        let mut key = uuids.to_vec();
+        key.sort();
+        key.dedup();
+        if let Some(unknown) = key.iter().find(|uuid| self.archive(uuid).is_none()) {
+            return Err(Error::InvalidArgument(format!(
+                "archive {} was not added",
+                unknown
+            )));
+        }
+        match self.searchers.iter().position(|(uuids, _)| *uuids == key) {
+            Some(position) => {
+                let searcher = self.searchers.remove(position);
+                self.searchers.push(searcher);
+            }
+            None => {
+                let searcher = self.new_searcher(&key)?;
+                if self.searchers.len() == MAX_CACHED_SEARCHERS {
+                    self.searchers.remove(0);
+                }
+                self.searchers.push((key, searcher));
+            }
+        }
+        Ok(&mut self.searchers.last_mut().unwrap().1)
+    }
+
+    /// A libzim searcher over the added archives in `uuids`, in the order they were added.
+    fn new_searcher(&self, uuids: &[Uuid]) -> Result<Searcher, Error> {
+        let mut archives = self
+            .archives
+            .iter()
+            .filter(|(uuid, _)| uuids.contains(uuid))
+            .map(|(_, archive)| archive);
+        let first = archives
+            .next()
+            .ok_or_else(|| Error::InvalidArgument("no archive to search".to_string()))?;
+        let mut searcher = Searcher::new(first)?;
+        for archive in archives {
+            searcher.add_archive(archive);
+        }
+        searcher.set_verbose(self.verbose);
+        Ok(searcher)
+    }
+}
+
 impl Query {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Query>) -> Result<Query, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Query { ptr }),
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Query>, text: Arc<str>) -> Query {
+        Query {
+            ptr,
+            text,
+            georange: None,
+            path_prefix: None,
+            mimetype: None,
+            max_results: None,
         }
     }
 
@@ -62,45 +337,342 @@ impl Query {
         self.ptr.as_ref().unwrap()
     }
 
-    pub fn new(query: &str) -> Result<Query, ()> {
-        Query::from_ptr(ffi::query_ctor(query))
+    pub fn new(query: impl Into<Arc<str>>) -> Result<Query, Error> {
+        let text = query.into();
+        Ok(Query::from_ptr(ffi::query_ctor(&text), text))
     }
 
-    pub fn set_query(&mut self, query: &str) {
-        ffi::query_setQuery(self.ptr.pin_mut(), query)
+    pub fn builder(query: impl Into<Arc<str>>) -> QueryBuilder {
+        QueryBuilder::new(query)
     }
 
+    pub fn set_query(&mut self, query: impl Into<Arc<str>>) {
+        self.text = query.into();
+        ffi::query_setQuery(self.ptr.pin_mut(), &self.text);
+    }
+
+    /// Restrict results to articles within `distance` meters of a point.
     pub fn set_georange(&mut self, latitude: f32, longitude: f32, distance: f32) {
-        ffi::query_setGeorange(self.ptr.pin_mut(), latitude, longitude, distance)
+        ffi::query_setGeorange(self.ptr.pin_mut(), latitude, longitude, distance);
+        self.georange = Some((latitude, longitude, distance));
+    }
+
+    pub fn text(&self) -> &Arc<str> {
+        &self.text
+    }
+
+    /// `(latitude, longitude, distance in meters)`.
+    pub fn georange(&self) -> Option<(f32, f32, f32)> {
+        self.georange
+    }
+
+    pub fn path_prefix(&self) -> Option<&str> {
+        self.path_prefix.as_deref()
+    }
+
+    pub fn mimetype(&self) -> Option<&str> {
+        self.mimetype.as_deref()
+    }
+
+    pub fn max_results(&self) -> Option<usize> {
+        self.max_results
+    }
+}
+
+impl Clone for Query {
+    fn clone(&self) -> Self {
+        let mut query = Query::from_ptr(ffi::query_ctor(&self.text), self.text.clone());
+        if let Some((latitude, longitude, distance)) = self.georange {
+            query.set_georange(latitude, longitude, distance);
+        }
+        query.path_prefix = self.path_prefix.clone();
+        query.mimetype = self.mimetype.clone();
+        query.max_results = self.max_results;
+        query
+    }
+}
+
+impl QueryBuilder {
+    pub fn new(query: impl Into<Arc<str>>) -> QueryBuilder {
+        QueryBuilder {
+            text: query.into(),
+            georange: None,
+            path_prefix: None,
+            mimetype: None,
+            max_results: None,
+        }
+    }
+
+    /// Only articles within `distance` of the point at `latitude`, `longitude` (in degrees).
+    pub fn georange(
+        mut self,
+        latitude: f32,
+        longitude: f32,
+        distance: f32,
+        unit: DistanceUnit,
+    ) -> QueryBuilder {
+        self.georange = Some((latitude, longitude, distance, unit));
+        self
+    }
+
+    /// Only results whose path starts with `prefix`.
+    pub fn path_prefix(mut self, prefix: &str) -> QueryBuilder {
+        self.path_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Only results whose item, once redirects are followed, has this mimetype.
+    pub fn mimetype(mut self, mimetype: &str) -> QueryBuilder {
+        self.mimetype = Some(mimetype.to_string());
+        self
+    }
+
+    /// At most `max_results` results in total, however they are fetched.
+    pub fn max_results(mut self, max_results: usize) -> QueryBuilder {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    pub fn build(&self) -> Result<Query, Error> {
+        let mut query = Query::new(self.text.clone())?;
+        if let Some((latitude, longitude, distance, unit)) = self.georange {
+            if !(-90.0..=90.0).contains(&latitude) {
+                return Err(Error::InvalidArgument(format!(
+                    "latitude {} is not between -90 and 90",
+                    latitude
+                )));
+            }
+            if !(-180.0..=180.0).contains(&longitude) {
+                return Err(Error::InvalidArgument(format!(
+                    "longitude {} is not between -180 and 180",
+                    longitude
+                )));
+            }
+            if !distance.is_finite() || distance <= 0.0 {
+                return Err(Error::InvalidArgument(format!(
+                    "distance {} is not a positive number",
+                    distance
+                )));
+            }
+            query.set_georange(latitude, longitude, unit.to_meters(distance));
+        }
+        let restrictions = [
+            ("path prefix", &self.path_prefix),
+            ("mimetype", &self.mimetype),
+        ];
+        for (name, value) in restrictions {
+            if value.as_deref().unwrap_or_default().len() > MAX_RESTRICTION_LEN {
+                return Err(Error::InvalidArgument(format!(
+                    "{} is longer than {} bytes",
+                    name, MAX_RESTRICTION_LEN
+                )));
+            }
+        }
+        query.path_prefix = self.path_prefix.clone();
+        query.mimetype = self.mimetype.clone();
+        query.max_results = self.max_results;
+        Ok(query)
+    }
+}
+
+impl DistanceUnit {
+    pub fn to_meters(self, distance: f32) -> f32 {
+        match self {
+            DistanceUnit::Meters => distance,
+            DistanceUnit::Kilometers => distance * 1000.0,
+            DistanceUnit::Miles => distance * 1609.344,
+        }
     }
 }
 
 impl Search {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Search>) -> Result<Search, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Search { ptr }),
+    pub(crate) fn from_ptr(
+        ptr: UniquePtr<ffi::Search>,
+        archives: &[Archive],
+        cursor: SearchCursor,
+    ) -> Search {
+        Search {
+            ptr: Rc::new(ptr),
+            archives: archives.to_vec(),
+            cursor,
+            filtered: RefCell::default(),
         }
     }
 
     fn inner_ref(&self) -> &ffi::Search {
-        self.ptr.as_ref().unwrap()
+        UniquePtr::as_ref(&self.ptr).unwrap()
+    }
+
+    /// At most `max_results` results, skipping the first `start` ones.
+    ///
+    /// Results outside the path prefix or mimetype the query is restricted to are
+    /// skipped and not counted in `start`. libzim cannot apply these restrictions, so
+    /// finding the requested results reads the entry of every result before them,
+    /// once for all the calls on this search.
+    ///
+    /// No result is returned past the limit of the query, see
+    /// [`QueryBuilder::max_results`].
+    pub fn get_results(&self, start: usize, max_results: usize) -> Result<SearchResultSet, Error> {
+        let max_results = max_results.min(self.limit().saturating_sub(start));
+        if !self.is_restricted() {
+            return self.raw_results(start, max_results);
+        }
+        let (raw_start, keep) = self.restricted_window(start, max_results)?;
+        let mut set = self.raw_results(raw_start, keep.len())?;
+        set.keep = Some(keep);
+        Ok(set)
+    }
+
+    /// Estimated number of results, at most the limit of the query.
+    ///
+    /// With a path prefix or mimetype restriction this is more approximate still:
+    /// libzim's estimate is scaled by the share of its first 64 results that pass the
+    /// restrictions. It is exact once all the results of libzim have been checked,
+    /// which is the case when there are fewer than 64 of them.
+    pub fn get_estimated_matches(&self) -> Result<usize, Error> {
+        let estimate = usize_from_int(ffi::search_getEstimatedMatches(self.inner_ref())?);
+        let estimate = match self.is_restricted() {
+            true => {
+                let filtered = self.filter_until(0, RESTRICTED_PAGE_SIZE)?;
+                match filtered.done {
+                    true => filtered.accepted.len(),
+                    false => {
+                        let sample = filtered
+                            .accepted
+                            .iter()
+                            .take_while(|&&raw| raw < RESTRICTED_PAGE_SIZE)
+                            .count();
+                        (estimate as u64 * sample as u64 / RESTRICTED_PAGE_SIZE as u64) as usize
+                    }
+                }
+            }
+            false => estimate,
+        };
+        Ok(estimate.min(self.limit()))
+    }
+
+    /// A cursor to continue this search at `offset` later, possibly from another process,
+    /// see [`Searcher::resume`].
+    ///
+    /// The uuids of the searched archives are only read here. Like `start` in
+    /// [`Search::get_results`], `offset` only counts results passing the restrictions.
+    pub fn cursor(&self, offset: usize) -> Result<SearchCursor, Error> {
+        self.cursor.with_archives(&self.archives, offset)
+    }
+
+    /// Every result, fetched lazily from libzim `page_size` results at a time.
+    ///
+    /// Iteration stops at the real end of the results, or at the limit of the query,
+    /// rather than at [`Search::get_estimated_matches`], which is only an estimate.
+    /// Results outside
+    /// the path prefix or mimetype the query is restricted to are skipped.
+    pub fn iter_all(&self, page_size: usize) -> SearchPages<'_> {
+        SearchPages {
+            search: self,
+            page_size: page_size.max(1),
+            next_start: 0,
+            page: SearchIterator::default(),
+            done: false,
+            remaining: self.limit(),
+        }
+    }
+
+    /// The results of libzim as-is, without applying restrictions.
+    fn raw_results(&self, start: usize, max_results: usize) -> Result<SearchResultSet, Error> {
+        let set = ffi::search_getResults(
+            self.inner_ref(),
+            int_from_usize(start, "start")?,
+            int_from_usize(max_results, "max_results")?,
+        )?;
+        Ok(SearchResultSet::from_ptr(
+            set,
+            &self.ptr,
+            start,
+            &self.archives,
+        ))
+    }
+
+    fn is_restricted(&self) -> bool {
+        self.cursor.path_prefix().is_some() || self.cursor.mimetype().is_some()
+    }
+
+    /// The limit of the query, `usize::MAX` without one.
+    fn limit(&self) -> usize {
+        self.cursor.max_results().unwrap_or(usize::MAX)
+    }
+
+    /// Where the `max_results` accepted results after the first `start` ones are
+    /// among the results of libzim: the raw offset of the first one, and whether
+    /// each raw result from there to the last one is accepted.
+    fn restricted_window(
+        &self,
+        start: usize,
+        max_results: usize,
+    ) -> Result<(usize, VecDeque<bool>), Error> {
+        let filtered = self.filter_until(start.saturating_add(max_results), 0)?;
+        let window = filtered.accepted.get(start..).unwrap_or_default();
+        let window = &window[..window.len().min(max_results)];
+        let (Some(&first), Some(&last)) = (window.first(), window.last()) else {
+            return Ok((filtered.checked, VecDeque::new()));
+        };
+        let mut keep = VecDeque::from(vec![false; last + 1 - first]);
+        for &raw in window {
+            keep[raw - first] = true;
+        }
+        Ok((first, keep))
     }
 
-    pub fn get_results(&self, start: i32, max_results: i32) -> Result<SearchResultSet, ()> {
-        SearchResultSet::from_ptr(ffi::search_getResults(self.inner_ref(), start, max_results))
+    /// Check the results of libzim against the restrictions, a page at a time, until
+    /// `accepted` of them passed and `checked` were checked, or all were.
+    fn filter_until(&self, accepted: usize, checked: usize) -> Result<RefMut<'_, Filtered>, Error> {
+        let mut filtered = self.filtered.borrow_mut();
+        while !filtered.done && (filtered.accepted.len() < accepted || filtered.checked < checked) {
+            let mut page = self
+                .raw_results(filtered.checked, RESTRICTED_PAGE_SIZE)?
+                .into_iter();
+            let page_len = page.len();
+            while let Some(entry) = page.next_entry() {
+                if self.accepts(&entry?)? {
+                    let raw = filtered.checked;
+                    filtered.accepted.push(raw);
+                }
+                filtered.checked += 1;
+            }
+            filtered.done = page_len < RESTRICTED_PAGE_SIZE;
+        }
+        Ok(filtered)
     }
 
-    pub fn get_estimated_matches(&self) -> i32 {
-        ffi::search_getEstimatedMatches(self.inner_ref())
+    /// Whether `entry` passes the restrictions libzim cannot apply itself. Only the
+    /// mimetype restriction reads the item of the entry.
+    fn accepts(&self, entry: &Entry) -> Result<bool, Error> {
+        if let Some(prefix) = self.cursor.path_prefix() {
+            if !entry.get_path().starts_with(prefix) {
+                return Ok(false);
+            }
+        }
+        if let Some(mimetype) = self.cursor.mimetype() {
+            if entry.get_item(true)?.get_mimetype()? != mimetype {
+                return Ok(false);
+            }
+        }
+        Ok(true)
     }
 }
 
 impl SearchResultSet {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::SearchResultSet>) -> Result<SearchResultSet, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(SearchResultSet { ptr }),
+    pub(crate) fn from_ptr(
+        ptr: UniquePtr<ffi::SearchResultSet>,
+        search: &Rc<UniquePtr<ffi::Search>>,
+        start: usize,
+        archives: &[Archive],
+    ) -> SearchResultSet {
+        SearchResultSet {
+            ptr,
+            search: search.clone(),
+            start,
+            archives: archives.to_vec(),
+            keep: None,
         }
     }
 
@@ -110,30 +682,31 @@ impl SearchResultSet {
 }
 
 impl IntoIterator for SearchResultSet {
-    type Item = Result<Entry, ()>;
+    type Item = Result<SearchResult, Error>;
 
     type IntoIter = SearchIterator;
 
     fn into_iter(self) -> Self::IntoIter {
-        match SearchIterator::from_searchresultset(self) {
-            Ok(iterator) => iterator,
-            Err(_) => SearchIterator::default(),
-        }
+        SearchIterator::from_searchresultset(self)
     }
 }
 
 impl SearchIterator {
-    pub(crate) fn from_searchresultset(set: SearchResultSet) -> Result<SearchIterator, ()> {
-        let cur = ffi::searchresultset_begin(set.inner_ref());
-        let end = ffi::searchresultset_end(set.inner_ref());
-        let size = match ffi::searchresultset_size(set.inner_ref()) {
-            n if n < 0 => 0,
-            x => x as usize,
+    pub(crate) fn from_searchresultset(set: SearchResultSet) -> SearchIterator {
+        let raw_size = usize_from_int(ffi::searchresultset_size(set.inner_ref()));
+        let size = match &set.keep {
+            Some(keep) => keep.iter().filter(|&&kept| kept).count(),
+            None => raw_size,
         };
-
-        match (cur.is_null(), end.is_null()) {
-            (false, false) => Ok(SearchIterator { cur, end, size }),
-            _ => Err(()),
+        SearchIterator {
+            cur: ffi::searchresultset_begin(set.inner_ref()),
+            end: ffi::searchresultset_end(set.inner_ref()),
+            size,
+            search: Some(set.search),
+            raw_start: set.start,
+            raw_end: set.start + raw_size,
+            archives: set.archives,
+            keep: set.keep,
         }
     }
 
@@ -144,6 +717,99 @@ impl SearchIterator {
     fn end_ref(&self) -> &ffi::SearchIterator {
         self.end.as_ref().unwrap()
     }
+
+    fn result_at(&self, it: &ffi::SearchIterator) -> Result<SearchResult, Error> {
+        let file_index = ffi::searchiterator_getFileIndex(it);
+        let (file_index, archive) = usize::try_from(file_index)
+            .ok()
+            .and_then(|index| Some((index, self.archives.get(index)?)))
+            .ok_or_else(|| {
+                Error::Libzim(format!("search result from unknown archive {}", file_index))
+            })?;
+        let entry = ffi::searchiterator_operator_star(it)?;
+        Ok(SearchResult {
+            entry: Entry::from_ptr(entry, archive),
+            score: ffi::searchiterator_getScore(it),
+            snippet: string_from_ptr(ffi::searchiterator_getSnippet(it)?),
+            word_count: u32::try_from(ffi::searchiterator_getWordCount(it)?).ok(),
+            size: u64::try_from(ffi::searchiterator_getSize(it)?).ok(),
+            file_index,
+            zim_id: Uuid::from_ptr(ffi::searchiterator_getZimId(it)?)?,
+        })
+    }
+
+    fn at_end(&self) -> bool {
+        self.size == 0 || ffi::searchiterator_operator_eq(self.cur_ref(), self.end_ref())
+    }
+
+    /// Move past the results that are not kept, false at the end.
+    fn skip_rejected(&mut self) -> bool {
+        while !self.at_end() {
+            match self.keep.as_mut() {
+                Some(keep) if keep.front() != Some(&true) => {
+                    keep.pop_front();
+                    ffi::searchiterator_operator_inc(self.cur.pin_mut());
+                    self.raw_start += 1;
+                }
+                _ => return true,
+            }
+        }
+        false
+    }
+
+    /// Move past the current result, which is kept.
+    fn step(&mut self) {
+        if let Some(keep) = self.keep.as_mut() {
+            keep.pop_front();
+        }
+        ffi::searchiterator_operator_inc(self.cur.pin_mut());
+        self.raw_start += 1;
+        self.size -= 1;
+    }
+
+    /// Skip the next `n` kept results, fewer than `size`, by fetching the results
+    /// after them from libzim rather than stepping over them.
+    fn skip_kept(&mut self, n: usize) -> Result<(), Error> {
+        let skipped = match self.keep.as_mut() {
+            Some(keep) => {
+                let mut kept = 0;
+                let skipped = keep
+                    .iter()
+                    .position(|&accepted| {
+                        kept += usize::from(accepted);
+                        kept > n
+                    })
+                    .unwrap_or(keep.len());
+                keep.drain(..skipped);
+                skipped
+            }
+            None => n,
+        };
+        let Some(search) = self.search.as_deref().and_then(UniquePtr::as_ref) else {
+            return Ok(());
+        };
+        let start = self.raw_start + skipped;
+        let set = ffi::search_getResults(
+            search,
+            int_from_usize(start, "start")?,
+            int_from_usize(self.raw_end - start, "max_results")?,
+        )?;
+        self.cur = ffi::searchresultset_begin(set.as_ref().unwrap());
+        self.end = ffi::searchresultset_end(set.as_ref().unwrap());
+        self.raw_start = start;
+        self.size -= n;
+        Ok(())
+    }
+
+    /// The entry of the next result, without loading its snippet or counts.
+    fn next_entry(&mut self) -> Option<Result<Entry, Error>> {
+        if !self.skip_rejected() {
+            return None;
+        }
+        let entry = self.result_at(self.cur_ref()).map(SearchResult::into_entry);
+        self.step();
+        Some(entry)
+    }
 }
 
 impl Default for SearchIterator {
@@ -152,23 +818,153 @@ impl Default for SearchIterator {
             cur: UniquePtr::null(),
             end: UniquePtr::null(),
             size: 0,
+            search: None,
+            raw_start: 0,
+            raw_end: 0,
+            archives: Vec::new(),
+            keep: None,
         }
     }
 }
 
 impl Iterator for SearchIterator {
-    type Item = Result<Entry, ()>;
+    type Item = Result<SearchResult, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.skip_rejected() {
+            return None;
+        }
+        let item = self.result_at(self.cur_ref());
+        self.step();
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.size, Some(self.size))
+    }
+
+    /// Skipped results are never loaded: the remaining ones are fetched again from
+    /// libzim, starting after them.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if n >= self.size {
+            self.size = 0;
+            return None;
+        }
+        if n > 0 {
+            if let Err(err) = self.skip_kept(n) {
+                self.size = 0;
+                return Some(Err(err));
+            }
+        }
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for SearchIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while !self.at_end() {
+            ffi::searchiterator_operator_dec(self.end.pin_mut());
+            self.raw_end -= 1;
+            let kept = self
+                .keep
+                .as_mut()
+                .is_none_or(|keep| keep.pop_back() == Some(true));
+            if kept {
+                self.size -= 1;
+                return Some(self.result_at(self.end_ref()));
+            }
+        }
+        None
+    }
+}
+
+impl ExactSizeIterator for SearchIterator {}
+
+impl Iterator for SearchPages<'_> {
+    type Item = Result<SearchResult, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let at_end =
-            self.size == 0 || ffi::searchiterator_operator_eq(self.cur_ref(), self.end_ref());
-        match at_end {
-            true => None,
-            false => {
-                let item = Entry::from_ptr(ffi::searchiterator_operator_star(self.cur_ref()));
-                ffi::searchiterator_operator_inc(self.cur.pin_mut());
-                Some(item)
+        loop {
+            if self.remaining == 0 {
+                return None;
+            }
+            if let Some(result) = self.page.next() {
+                match result.and_then(|r| Ok((self.search.accepts(r.entry())?, r))) {
+                    Ok((true, result)) => {
+                        self.remaining -= 1;
+                        return Some(Ok(result));
+                    }
+                    Ok((false, _)) => continue,
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            if self.done {
+                return None;
+            }
+            match self.search.raw_results(self.next_start, self.page_size) {
+                Ok(set) => {
+                    self.page = set.into_iter();
+                    // A short page is the last one.
+                    self.done = self.page.len() < self.page_size;
+                    self.next_start += self.page.len();
+                }
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
             }
         }
     }
 }
+
+impl std::iter::FusedIterator for SearchPages<'_> {}
+
+impl SearchResult {
+    pub fn entry(&self) -> &Entry {
+        &self.entry
+    }
+
+    pub fn into_entry(self) -> Entry {
+        self.entry
+    }
+
+    /// Relevance of the match, as a percentage.
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    /// Extract of the content around the matched terms, which are wrapped in `<b>` tags.
+    pub fn snippet(&self) -> &str {
+        &self.snippet
+    }
+
+    /// Number of words of the matched article, if the index stores it.
+    pub fn word_count(&self) -> Option<u32> {
+        self.word_count
+    }
+
+    /// Size of the matched article in bytes, if the index stores it.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Position of the source archive among the archives searched, in the order they
+    /// were added to the [`Searcher`].
+    ///
+    /// A [`MultiSearcher`] only searches the archives selected by
+    /// [`MultiSearcher::search_in`], so this is not a position among all of its
+    /// archives: use [`SearchResult::zim_id`] or [`SearchResult::archive`] instead.
+    pub fn file_index(&self) -> usize {
+        self.file_index
+    }
+
+    /// Uuid of the source archive.
+    pub fn zim_id(&self) -> Uuid {
+        self.zim_id
+    }
+
+    /// The source archive.
+    pub fn archive(&self) -> &Archive {
+        self.entry.archive()
+    }
+}
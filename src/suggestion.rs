@@ -1,46 +1,97 @@
+use std::{collections::HashMap, rc::Rc};
+
 use cxx::UniquePtr;
-use zim_sys::binding::ffi;
 
-use crate::{archive::Archive, cxx::string_from_ptr};
+use crate::{
+    archive::Archive,
+    binding::ffi,
+    cursor::SearchCursor,
+    cxx::{int_from_usize, string_from_ptr, usize_from_int},
+    entry::Entry,
+    error::Error,
+    item::Item,
+};
+
+/// Redirects followed when folding suggestions before giving up on a chain.
+const MAX_REDIRECTS: usize = 16;
+
+/// Suggestions fetched at a time while folding.
+const FOLD_PAGE_SIZE: usize = 64;
 
 pub struct SuggestionSearcher {
     ptr: UniquePtr<ffi::SuggestionSearcher>,
+    archive: Archive,
 }
 
 pub struct SuggestionSearch {
-    ptr: UniquePtr<ffi::SuggestionSearch>,
+    /// Shared with the iterators over its results, which fetch results again to skip
+    /// ahead.
+    ptr: Rc<UniquePtr<ffi::SuggestionSearch>>,
+    archive: Archive,
+    cursor: SearchCursor,
 }
 
 pub struct SuggestionResultSet {
     ptr: UniquePtr<ffi::SuggestionResultSet>,
+    search: Rc<UniquePtr<ffi::SuggestionSearch>>,
+    /// Offset of the first suggestion of the set among all suggestions.
+    start: usize,
+    archive: Archive,
 }
 
 pub struct SuggestionIterator {
     cur: UniquePtr<ffi::SuggestionIterator>,
     end: UniquePtr<ffi::SuggestionIterator>,
     size: usize,
+    /// The search the suggestions come from, `None` for an empty iterator.
+    search: Option<Rc<UniquePtr<ffi::SuggestionSearch>>>,
+    /// Offset of `cur` among all suggestions.
+    start: usize,
+    /// The suggested archive, `None` for an empty iterator.
+    archive: Option<Archive>,
 }
 
 pub struct SuggestionItem {
     ptr: UniquePtr<ffi::SuggestionItem>,
+    archive: Archive,
+}
+
+/// An article suggested once, whether it matched directly or through redirects,
+/// see [`SuggestionSearch::get_folded_results`].
+pub struct FoldedSuggestion {
+    entry: Entry,
+    aliases: Vec<String>,
+    snippet: Option<String>,
+    resolve_error: Option<Error>,
 }
 
 impl SuggestionSearcher {
     pub(crate) fn from_ptr(
         ptr: UniquePtr<ffi::SuggestionSearcher>,
-    ) -> Result<SuggestionSearcher, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(SuggestionSearcher { ptr }),
+        archive: &Archive,
+    ) -> SuggestionSearcher {
+        SuggestionSearcher {
+            ptr,
+            archive: archive.clone(),
         }
     }
 
-    pub fn new(archive: &Archive) -> Result<SuggestionSearcher, ()> {
-        SuggestionSearcher::from_ptr(ffi::suggestionsearcher_ctor(archive.inner_ref()))
+    pub fn new(archive: &Archive) -> Result<SuggestionSearcher, Error> {
+        let ptr = ffi::suggestionsearcher_ctor(archive.inner_ref())?;
+        Ok(SuggestionSearcher::from_ptr(ptr, archive))
     }
 
-    pub fn suggest(&mut self, query: &str) -> Result<SuggestionSearch, ()> {
-        SuggestionSearch::from_ptr(ffi::suggestionsearcher_suggest(self.ptr.pin_mut(), query))
+    pub fn suggest(&mut self, query: &str) -> Result<SuggestionSearch, Error> {
+        let cursor = SearchCursor::new(query, None, Vec::new(), 0);
+        let ptr = ffi::suggestionsearcher_suggest(self.ptr.pin_mut(), query)?;
+        Ok(SuggestionSearch::from_ptr(ptr, &self.archive, cursor))
+    }
+
+    /// Rebuild the suggestion search `cursor` was taken from, which must have been
+    /// made on this archive. Results should then be fetched from [`SearchCursor::offset`].
+    pub fn resume(&mut self, cursor: &SearchCursor) -> Result<SuggestionSearch, Error> {
+        cursor.check_archives(std::slice::from_ref(&self.archive))?;
+        self.suggest(cursor.query())
     }
 
     pub fn set_verbose(&mut self, verbose: bool) {
@@ -49,37 +100,133 @@ impl SuggestionSearcher {
 }
 
 impl SuggestionSearch {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::SuggestionSearch>) -> Result<SuggestionSearch, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(SuggestionSearch { ptr }),
+    pub(crate) fn from_ptr(
+        ptr: UniquePtr<ffi::SuggestionSearch>,
+        archive: &Archive,
+        cursor: SearchCursor,
+    ) -> SuggestionSearch {
+        SuggestionSearch {
+            ptr: Rc::new(ptr),
+            archive: archive.clone(),
+            cursor,
         }
     }
 
     fn inner_ref(&self) -> &ffi::SuggestionSearch {
-        self.ptr.as_ref().unwrap()
+        UniquePtr::as_ref(&self.ptr).unwrap()
     }
 
-    pub fn get_results(&self, start: i32, max_results: i32) -> Result<SuggestionResultSet, ()> {
-        SuggestionResultSet::from_ptr(ffi::suggestionsearch_getResults(
+    /// At most `max_results` suggestions, skipping the first `start` ones.
+    pub fn get_results(
+        &self,
+        start: usize,
+        max_results: usize,
+    ) -> Result<SuggestionResultSet, Error> {
+        let set = ffi::suggestionsearch_getResults(
             self.inner_ref(),
+            int_from_usize(start, "start")?,
+            int_from_usize(max_results, "max_results")?,
+        )?;
+        Ok(SuggestionResultSet::from_ptr(
+            set,
+            &self.ptr,
             start,
-            max_results,
+            &self.archive,
         ))
     }
 
-    pub fn get_estimated_matches(&self) -> i32 {
-        ffi::suggestionsearch_getEstimatedMatches(self.inner_ref())
+    /// Like [`SuggestionSearch::get_results`], but suggestions leading to the same
+    /// article once redirects are followed are folded together, in the position of
+    /// the best ranked one.
+    ///
+    /// `start` and `max_results` count folded articles, so suggestions are folded
+    /// from the first one and an article is never repeated on a later page. The
+    /// aliases of an article are the redirects ranked before the end of its page.
+    /// A redirect that cannot be resolved is not folded, see
+    /// [`FoldedSuggestion::resolve_error`].
+    pub fn get_folded_results(
+        &self,
+        start: usize,
+        max_results: usize,
+    ) -> Result<Vec<FoldedSuggestion>, Error> {
+        let end = start.saturating_add(max_results);
+        let mut folded: Vec<FoldedSuggestion> = Vec::new();
+        let mut positions = HashMap::new();
+        let mut next = 0;
+        'pages: loop {
+            let page = self.get_results(next, FOLD_PAGE_SIZE)?.into_iter();
+            let last_page = page.len() < FOLD_PAGE_SIZE;
+            next += page.len();
+            for suggestion in page {
+                let suggestion = suggestion?;
+                let entry = suggestion.entry()?;
+                let is_redirect = entry.is_redirect();
+                let (key, target, resolve_error) = match entry.resolve(MAX_REDIRECTS) {
+                    Ok(chain) => (Ok(chain.target.get_index()), chain.target, None),
+                    Err(err) => (Err(entry.get_index()), entry, Some(err)),
+                };
+                let position = match positions.get(&key) {
+                    Some(&position) => position,
+                    None if folded.len() == end => break 'pages,
+                    None => {
+                        folded.push(FoldedSuggestion {
+                            entry: target,
+                            aliases: Vec::new(),
+                            snippet: None,
+                            resolve_error,
+                        });
+                        positions.insert(key, folded.len() - 1);
+                        folded.len() - 1
+                    }
+                };
+                let group = &mut folded[position];
+                match is_redirect {
+                    true if group.resolve_error.is_none() => {
+                        let title = suggestion.get_title();
+                        if !group.aliases.contains(&title) {
+                            group.aliases.push(title);
+                        }
+                    }
+                    true => {}
+                    false if suggestion.has_snippet() => {
+                        group.snippet = Some(suggestion.get_snippet());
+                    }
+                    false => {}
+                }
+            }
+            if last_page {
+                break;
+            }
+        }
+        Ok(folded.split_off(start.min(folded.len())))
+    }
+
+    pub fn get_estimated_matches(&self) -> Result<usize, Error> {
+        Ok(usize_from_int(ffi::suggestionsearch_getEstimatedMatches(
+            self.inner_ref(),
+        )?))
+    }
+
+    /// A cursor to continue these suggestions at `offset` later,
+    /// see [`SuggestionSearcher::resume`].
+    pub fn cursor(&self, offset: usize) -> Result<SearchCursor, Error> {
+        self.cursor
+            .with_archives(std::slice::from_ref(&self.archive), offset)
     }
 }
 
 impl SuggestionResultSet {
     pub(crate) fn from_ptr(
         ptr: UniquePtr<ffi::SuggestionResultSet>,
-    ) -> Result<SuggestionResultSet, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(SuggestionResultSet { ptr }),
+        search: &Rc<UniquePtr<ffi::SuggestionSearch>>,
+        start: usize,
+        archive: &Archive,
+    ) -> SuggestionResultSet {
+        SuggestionResultSet {
+            ptr,
+            search: search.clone(),
+            start,
+            archive: archive.clone(),
         }
     }
 
@@ -89,30 +236,24 @@ impl SuggestionResultSet {
 }
 
 impl IntoIterator for SuggestionResultSet {
-    type Item = Result<SuggestionItem, ()>;
+    type Item = Result<SuggestionItem, Error>;
 
     type IntoIter = SuggestionIterator;
 
     fn into_iter(self) -> Self::IntoIter {
-        match SuggestionIterator::from_searchresultset(self) {
-            Ok(iterator) => iterator,
-            Err(_) => SuggestionIterator::default(),
-        }
+        SuggestionIterator::from_searchresultset(self)
     }
 }
 
 impl SuggestionIterator {
-    pub(crate) fn from_searchresultset(set: SuggestionResultSet) -> Result<SuggestionIterator, ()> {
-        let cur = ffi::suggestionresultset_begin(set.inner_ref());
-        let end = ffi::suggestionresultset_end(set.inner_ref());
-        let size = match ffi::suggestionresultset_size(set.inner_ref()) {
-            n if n < 0 => 0,
-            x => x as usize,
-        };
-
-        match (cur.is_null(), end.is_null()) {
-            (false, false) => Ok(SuggestionIterator { cur, end, size }),
-            _ => Err(()),
+    pub(crate) fn from_searchresultset(set: SuggestionResultSet) -> SuggestionIterator {
+        SuggestionIterator {
+            cur: ffi::suggestionresultset_begin(set.inner_ref()),
+            end: ffi::suggestionresultset_end(set.inner_ref()),
+            size: usize_from_int(ffi::suggestionresultset_size(set.inner_ref())),
+            search: Some(set.search),
+            start: set.start,
+            archive: Some(set.archive),
         }
     }
 
@@ -123,6 +264,33 @@ impl SuggestionIterator {
     fn end_ref(&self) -> &ffi::SuggestionIterator {
         self.end.as_ref().unwrap()
     }
+
+    fn at_end(&self) -> bool {
+        self.size == 0 || ffi::suggestioniterator_operator_eq(self.cur_ref(), self.end_ref())
+    }
+
+    /// Only called before the end, so with an archive.
+    fn item_from_ptr(&self, ptr: UniquePtr<ffi::SuggestionItem>) -> SuggestionItem {
+        SuggestionItem::from_ptr(ptr, self.archive.as_ref().unwrap())
+    }
+
+    /// Skip the next `n` suggestions, fewer than `size`, by fetching the suggestions
+    /// after them from libzim rather than stepping over them.
+    fn skip(&mut self, n: usize) -> Result<(), Error> {
+        let Some(search) = self.search.as_deref().and_then(UniquePtr::as_ref) else {
+            return Ok(());
+        };
+        let set = ffi::suggestionsearch_getResults(
+            search,
+            int_from_usize(self.start + n, "start")?,
+            int_from_usize(self.size - n, "max_results")?,
+        )?;
+        self.cur = ffi::suggestionresultset_begin(set.as_ref().unwrap());
+        self.end = ffi::suggestionresultset_end(set.as_ref().unwrap());
+        self.start += n;
+        self.size -= n;
+        Ok(())
+    }
 }
 
 impl Default for SuggestionIterator {
@@ -131,34 +299,78 @@ impl Default for SuggestionIterator {
             cur: UniquePtr::null(),
             end: UniquePtr::null(),
             size: 0,
+            search: None,
+            start: 0,
+            archive: None,
         }
     }
 }
 
 impl Iterator for SuggestionIterator {
-    type Item = Result<SuggestionItem, ()>;
+    type Item = Result<SuggestionItem, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let at_end =
-            self.size == 0 || ffi::suggestioniterator_operator_eq(self.cur_ref(), self.end_ref());
-        match at_end {
+        match self.at_end() {
             true => None,
             false => {
-                let item = SuggestionItem::from_ptr(ffi::suggestioniterator_operator_star(
-                    self.cur.pin_mut(),
-                ));
+                let item = ffi::suggestioniterator_operator_star(self.cur.pin_mut())
+                    .map(|ptr| self.item_from_ptr(ptr))
+                    .map_err(Error::from);
                 ffi::suggestioniterator_operator_inc(self.cur.pin_mut());
+                self.start += 1;
+                self.size -= 1;
+                Some(item)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.size, Some(self.size))
+    }
+
+    /// Skipped suggestions are never loaded: the remaining ones are fetched again
+    /// from libzim, starting after them.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if n >= self.size {
+            self.size = 0;
+            return None;
+        }
+        if n > 0 {
+            if let Err(err) = self.skip(n) {
+                self.size = 0;
+                return Some(Err(err));
+            }
+        }
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for SuggestionIterator {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.at_end() {
+            true => None,
+            false => {
+                ffi::suggestioniterator_operator_dec(self.end.pin_mut());
+                self.size -= 1;
+                let item = ffi::suggestioniterator_operator_star(self.end.pin_mut())
+                    .map(|ptr| self.item_from_ptr(ptr))
+                    .map_err(Error::from);
                 Some(item)
             }
         }
     }
 }
 
+impl ExactSizeIterator for SuggestionIterator {}
+
 impl SuggestionItem {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::SuggestionItem>) -> Result<SuggestionItem, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(SuggestionItem { ptr }),
+    pub(crate) fn from_ptr(
+        ptr: UniquePtr<ffi::SuggestionItem>,
+        archive: &Archive,
+    ) -> SuggestionItem {
+        SuggestionItem {
+            ptr,
+            archive: archive.clone(),
         }
     }
 
@@ -168,20 +380,68 @@ impl SuggestionItem {
 
     pub fn get_title(&self) -> String {
         string_from_ptr(ffi::suggestionitem_getTitle(self.inner_ref()))
-            .expect("SuggestionItem::get_title should be infallible")
     }
 
     pub fn get_path(&self) -> String {
         string_from_ptr(ffi::suggestionitem_getPath(self.inner_ref()))
-            .expect("SuggestionItem::get_path should be infallible")
     }
 
     pub fn get_snippet(&self) -> String {
         string_from_ptr(ffi::suggestionitem_getSnippet(self.inner_ref()))
-            .expect("SuggestionItem::get_snippet should be infallible")
     }
 
     pub fn has_snippet(&self) -> bool {
         ffi::suggestionitem_hasSnippet(self.inner_ref())
     }
+
+    /// The suggested entry, looked up by path in the archive the suggestion was made
+    /// on. It may be a redirect.
+    pub fn entry(&self) -> Result<Entry, Error> {
+        self.archive.get_entry_bypath_str(&self.get_path())
+    }
+
+    /// The content of the suggested entry, following redirects.
+    pub fn item(&self) -> Result<Item, Error> {
+        self.entry()?.get_item(true)
+    }
+
+    /// The archive the suggestion was made on.
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+}
+
+impl FoldedSuggestion {
+    /// The suggested article, never a redirect unless it could not be resolved.
+    pub fn entry(&self) -> &Entry {
+        &self.entry
+    }
+
+    pub fn into_entry(self) -> Entry {
+        self.entry
+    }
+
+    pub fn get_title(&self) -> String {
+        self.entry.get_title()
+    }
+
+    pub fn get_path(&self) -> String {
+        self.entry.get_path()
+    }
+
+    /// Titles of the suggested redirects leading to this article, best ranked first.
+    pub fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    /// The snippet of the article's own suggestion, if it was suggested directly.
+    pub fn snippet(&self) -> Option<&str> {
+        self.snippet.as_deref()
+    }
+
+    /// Why the suggested redirect [`FoldedSuggestion::entry`] could not be followed
+    /// (a loop, a missing target, ...), in which case it was not folded.
+    pub fn resolve_error(&self) -> Option<&Error> {
+        self.resolve_error.as_ref()
+    }
 }
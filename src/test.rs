@@ -1,6 +1,11 @@
 use crate::archive::*;
+#[cfg(not(feature = "pure-rust"))]
 use crate::search::*;
+#[cfg(not(feature = "pure-rust"))]
 use crate::suggestion::*;
+#[cfg(not(feature = "pure-rust"))]
+use crate::writer::*;
+use crate::Error;
 
 pub static WIKT: &str = "/home/aka/Downloads/wiktionary_en_all_maxi_2022-09.zim";
 
@@ -11,21 +16,29 @@ fn test() {
 }
 
 #[test]
+#[cfg(not(feature = "pure-rust"))]
 fn search() {
     let a = Archive::new(WIKT).expect("File should exist!");
 
     let mut sr = Searcher::new(&a).expect("Searcher failed to create");
     let q = Query::new("name").expect("Query failed to create");
     let s = sr.search(&q).expect("Search failed to create");
-    println!("Estimated matches: {}", s.get_estimated_matches());
+    println!("Estimated matches: {}", s.get_estimated_matches().unwrap());
 
     let res = s.get_results(0, 10).expect("Search matches failed");
     let mut count = 0;
     for result in res {
         count += 1;
         match result {
-            Ok(entry) => {
-                println!("{}, [{}]", entry.get_title(), entry.get_index());
+            Ok(result) => {
+                let entry = result.entry();
+                println!(
+                    "{}, [{}] score {}: {}",
+                    entry.get_title(),
+                    entry.get_index(),
+                    result.score(),
+                    result.snippet()
+                );
             }
             Err(_) => println!("Entry error!"),
         }
@@ -34,6 +47,7 @@ fn search() {
 }
 
 #[test]
+#[cfg(not(feature = "pure-rust"))]
 fn suggest() {
     let a = Archive::new(WIKT).expect("File should exist!");
 
@@ -41,7 +55,7 @@ fn suggest() {
     let s = sr
         .suggest("name")
         .expect("SuggestionSearch failed to create");
-    println!("Estimated matches: {}", s.get_estimated_matches());
+    println!("Estimated matches: {}", s.get_estimated_matches().unwrap());
 
     let res = s
         .get_results(0, 10)
@@ -58,3 +72,1701 @@ fn suggest() {
     }
     assert_eq!(count, 10);
 }
+
+#[test]
+fn open_missing_file() {
+    match Archive::new("/nonexistent/archive.zim") {
+        Err(Error::Io(_)) => {}
+        other => panic!("Expected Io error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+fn open_not_a_zim() {
+    let path = std::env::temp_dir().join("zim-rs-not-a-zim.zim");
+    std::fs::write(&path, b"definitely not a zim archive").unwrap();
+    match Archive::new(path.to_str().unwrap()) {
+        Err(Error::ZimFileFormat(_)) => {}
+        other => panic!("Expected ZimFileFormat error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn open_not_a_zim() {
+    let path = std::env::temp_dir().join("zim-rs-not-a-zim-native.zim");
+    std::fs::write(&path, b"definitely not a zim archive").unwrap();
+    match Archive::new(path.to_str().unwrap()) {
+        Err(Error::InvalidFormat(_)) => {}
+        other => panic!("Expected InvalidFormat error, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn archive_shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>() {}
+    assert_send_sync::<Archive>();
+    assert_send::<crate::entry::Entry>();
+    assert_send::<crate::item::Item>();
+
+    let a = open_test_archive("zim-rs-shared.zim");
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let a = a.clone();
+            std::thread::spawn(move || a.get_mainentry().map(|e| e.get_path()).ok())
+        })
+        .collect();
+    let paths: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert!(paths[0].is_some());
+    assert!(paths.windows(2).all(|w| w[0] == w[1]));
+}
+
+#[test]
+fn open_with_config() {
+    let config = OpenConfig::new()
+        .dirent_cache_size(128)
+        .preload_dirents(256);
+    let path = write_test_archive("zim-rs-open_with_config.zim");
+    let a = Archive::open_with_config(&path, &config).expect("Archive failed to open");
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(a.get_dirent_cache_max_size(), 128);
+    assert_eq!(a.get_dirent_lookup_cache_max_size(), 256);
+
+    a.set_dirent_cache_max_size(16);
+    assert_eq!(a.get_dirent_cache_max_size(), 16);
+    assert!(a.get_dirent_cache_current_size() <= 16);
+}
+
+#[test]
+fn cluster_cache_size() {
+    let default = get_cluster_cache_max_size();
+    set_cluster_cache_max_size(64 << 20);
+    assert_eq!(get_cluster_cache_max_size(), 64 << 20);
+    set_cluster_cache_max_size(default);
+    assert!(get_cluster_cache_current_size() <= default);
+}
+
+#[test]
+fn open_from_fd() {
+    let path = write_test_archive("zim-rs-open_from_fd.zim");
+    let file = std::fs::File::open(&path).unwrap();
+    let a = Archive::from_fd(&file).expect("Archive failed to open from fd");
+    let b = Archive::new(path.to_str().unwrap()).unwrap();
+    assert_eq!(a.get_entrycount(), b.get_entrycount());
+    assert_eq!(a.get_entrycount(), 4);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_from_fd_range() {
+    use std::io::Write;
+
+    // Embed the archive after some unrelated data, like a bundle or an apk would.
+    let zim = test_archive();
+    let size = zim.len() as u64;
+    let padding = vec![0xAAu8; 12345];
+    let path = std::env::temp_dir().join("zim-rs-container.bin");
+    let mut container = std::fs::File::create(&path).unwrap();
+    container.write_all(&padding).unwrap();
+    container.write_all(&zim).unwrap();
+    container.write_all(&padding).unwrap();
+    drop(container);
+
+    let container = std::fs::File::open(&path).unwrap();
+    let a = Archive::from_fd_range(&container, padding.len() as u64, size)
+        .expect("Archive failed to open from fd range");
+    assert_eq!(a.get_all_entrycount(), 6);
+    assert_eq!(a.get_filesize(), size);
+    let item = a
+        .get_entry_bypath_str("style.css")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    assert_eq!(item.get_data().unwrap().data(), b"body {}");
+
+    match Archive::from_fd_range(&container, 1, size) {
+        #[cfg(feature = "pure-rust")]
+        Err(Error::InvalidFormat(_)) => {}
+        #[cfg(not(feature = "pure-rust"))]
+        Err(Error::ZimFileFormat(_)) => {}
+        other => panic!("Expected a format error, got {:?}", other.err()),
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_parts() {
+    let zim = test_archive();
+    let dir = std::env::temp_dir();
+    let whole = dir.join("zim-rs-whole.zim");
+    std::fs::write(&whole, &zim).unwrap();
+    let a = Archive::new(whole.to_str().unwrap()).unwrap();
+    assert_eq!(a.parts().len(), 1);
+    assert_eq!(a.parts()[0].range(), 0..zim.len() as u64);
+
+    // Split the archive in two parts that do not follow the .zimaa/.zimab naming.
+    let paths = [
+        dir.join("zim-rs-first.part"),
+        dir.join("zim-rs-second.part"),
+    ];
+    let half = zim.len() / 2;
+    std::fs::write(&paths[0], &zim[..half]).unwrap();
+    std::fs::write(&paths[1], &zim[half..]).unwrap();
+
+    let b = Archive::open_parts(&paths).expect("Archive failed to open from parts");
+    assert!(b.is_multipart());
+    assert_eq!(b.get_all_entrycount(), a.get_all_entrycount());
+    let parts = b.parts();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].path(), Some(paths[0].as_path()));
+    assert_eq!(parts[0].range(), 0..half as u64);
+    assert_eq!(parts[1].range(), 0..(zim.len() - half) as u64);
+    let last = b
+        .get_entry_bypath_str("z.txt")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    assert_eq!(last.get_data().unwrap().data(), b"last");
+
+    for path in paths.iter().chain([&whole]) {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn ordered_iterators() {
+    let a = open_test_archive("zim-rs-ordered_iterators.zim");
+
+    let paths: Vec<String> = a
+        .iter_by_path()
+        .expect("Path range failed")
+        .into_iter()
+        .map(|e| e.expect("Entry error!").get_path())
+        .collect();
+    assert_eq!(paths, ["main", "other", "style.css", "z.txt"]);
+
+    let titles: Vec<String> = a
+        .iter_by_title()
+        .expect("Title range failed")
+        .into_iter()
+        .map(|e| e.expect("Entry error!").get_title())
+        .collect();
+    assert_eq!(titles.len(), 4);
+    assert!(titles.windows(2).all(|w| w[0] <= w[1]));
+
+    let found: Vec<String> = a
+        .find_by_path("s")
+        .expect("Path range failed")
+        .into_iter()
+        .map(|e| e.expect("Entry error!").get_path())
+        .collect();
+    assert_eq!(found, ["style.css"]);
+
+    let mut count = 0;
+    for entry in a.find_by_title("Main").expect("Title range failed") {
+        assert!(entry.expect("Entry error!").get_title().starts_with("Main"));
+        count += 1;
+    }
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn entry_range_slicing() {
+    let a = open_test_archive("zim-rs-entry_range_slicing.zim");
+    let by_path = a.iter_by_path().expect("Path range failed");
+    assert_eq!(by_path.len(), a.get_entrycount() as usize);
+
+    let paths = |range: EntryRangePath| -> Vec<String> {
+        range
+            .into_iter()
+            .map(|e| e.expect("Entry error!").get_path())
+            .collect()
+    };
+    let slice = paths(by_path.range(1..3).expect("Slicing failed"));
+    let skipped: Vec<String> = a
+        .iter_by_path()
+        .unwrap()
+        .into_iter()
+        .skip(1)
+        .take(2)
+        .map(|e| e.expect("Entry error!").get_path())
+        .collect();
+    assert_eq!(slice, skipped);
+    assert!(by_path.range(1..by_path.len() + 1).is_err());
+
+    let mut iter = a.iter_by_path().unwrap().into_iter();
+    assert_eq!(iter.len(), by_path.len());
+    let last = iter.next_back().unwrap().unwrap();
+    let tail = paths(by_path.range(by_path.len() - 1..).unwrap());
+    assert_eq!(tail, vec![last.get_path()]);
+    assert_eq!(iter.len(), by_path.len() - 1);
+
+    let reversed: Vec<String> = by_path
+        .range(1..3)
+        .unwrap()
+        .into_iter()
+        .rev()
+        .map(|e| e.expect("Entry error!").get_path())
+        .collect();
+    assert!(reversed.iter().eq(slice.iter().rev()));
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+#[ignore = "needs the wiktionary archive at WIKT and its full-text index"]
+fn search_iterator_size() {
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let mut sr = Searcher::new(&a).expect("Searcher failed to create");
+    let q = Query::new("name").expect("Query failed to create");
+    let s = sr.search(&q).expect("Search failed to create");
+
+    let forward: Vec<String> = s
+        .get_results(0, 10)
+        .expect("Search matches failed")
+        .into_iter()
+        .map(|r| r.unwrap().entry().get_path())
+        .collect();
+    let mut iter = s.get_results(0, 10).unwrap().into_iter();
+    assert_eq!(iter.len(), 10);
+    let third = iter.nth(2).unwrap().unwrap().entry().get_path();
+    assert_eq!(forward[2], third);
+    assert_eq!(iter.len(), 7);
+    let last = iter.next_back().unwrap().unwrap().entry().get_path();
+    assert_eq!(forward[9], last);
+    let rest: Vec<String> = iter.map(|r| r.unwrap().entry().get_path()).collect();
+    assert_eq!(rest, forward[3..9]);
+    let backward: Vec<String> = s
+        .get_results(0, 10)
+        .unwrap()
+        .into_iter()
+        .rev()
+        .map(|r| r.unwrap().entry().get_path())
+        .collect();
+    assert!(backward.iter().eq(forward.iter().rev()));
+
+    let mut sr = SuggestionSearcher::new(&a).expect("SuggestionSearcher failed to create");
+    let s = sr
+        .suggest("name")
+        .expect("SuggestionSearch failed to create");
+    let forward: Vec<String> = s
+        .get_results(0, 10)
+        .expect("SuggestionSearch matches failed")
+        .into_iter()
+        .map(|r| r.unwrap().get_path())
+        .collect();
+    let mut iter = s.get_results(0, 10).unwrap().into_iter();
+    assert_eq!(iter.len(), 10);
+    assert_eq!(iter.nth(4).unwrap().unwrap().get_path(), forward[4]);
+    assert_eq!(iter.len(), 5);
+    let rest: Vec<String> = iter.map(|r| r.unwrap().get_path()).collect();
+    assert_eq!(rest, forward[5..]);
+}
+
+#[test]
+#[ignore = "needs the wiktionary archive at WIKT, the test archive has no illustration"]
+fn illustrations() {
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let sizes = a
+        .get_illustration_sizes()
+        .expect("Illustration sizes failed");
+    assert!(sizes.contains(&48));
+    assert!(a.has_illustration(48));
+
+    let item = a.get_illustration_item(48).expect("Favicon should exist");
+    assert_eq!(item.get_mimetype().unwrap(), "image/png");
+    let data = item.get_data().expect("Favicon data failed");
+    assert_eq!(&data.data()[..8], b"\x89PNG\r\n\x1a\n");
+
+    assert!(matches!(
+        a.get_illustration_item(7),
+        Err(Error::EntryNotFound(_))
+    ));
+}
+
+#[test]
+fn metadata() {
+    let a = open_test_archive("zim-rs-metadata.zim");
+    let m = a.metadata().expect("Metadata failed");
+    assert_eq!(m.title.as_deref(), Some("Test archive"));
+    assert_eq!(m.title, a.get_metadata("Title").ok());
+    assert!(m.language.is_empty());
+    assert_eq!(m.date, None);
+    assert!(m.other.is_empty());
+}
+
+#[test]
+fn metadata_date() {
+    use crate::metadata::Date;
+
+    let date: Date = "2022-09-13".parse().unwrap();
+    assert_eq!((date.year(), date.month(), date.day()), (2022, 9, 13));
+    assert_eq!(date.to_string(), "2022-09-13");
+    assert!("2022-9-13".parse::<Date>().is_err());
+    assert!("2023-02-29".parse::<Date>().is_err());
+    assert!("2024-02-29".parse::<Date>().is_ok());
+    assert!(Date::new(2022, 13, 1).is_none());
+}
+
+#[test]
+fn metadata_tags_counter() {
+    use crate::metadata::{Counter, Tags};
+
+    let tags: Tags = "wiktionary;_category:wiktionary;_pictures:no;_ftindex:yes;_details:yes"
+        .parse()
+        .unwrap();
+    assert_eq!(tags.ftindex(), Some(true));
+    assert_eq!(tags.pictures(), Some(false));
+    assert_eq!(tags.videos(), None);
+    assert_eq!(tags.details(), Some(true));
+    assert_eq!(tags.category(), Some("wiktionary"));
+    assert_eq!(tags.free().collect::<Vec<_>>(), vec!["wiktionary"]);
+
+    let counter: Counter = "text/html=120;image/png=3;application/javascript=2"
+        .parse()
+        .unwrap();
+    assert_eq!(counter.get("text/html"), 120);
+    assert_eq!(counter.get("video/webm"), 0);
+    assert_eq!(counter.total(), 125);
+    assert!("text/html=lots".parse::<Counter>().is_err());
+
+    let a = open_test_archive("zim-rs-metadata_tags_counter.zim");
+    assert!(matches!(a.tags(), Err(Error::EntryNotFound(_))));
+    assert!(matches!(a.counter(), Err(Error::EntryNotFound(_))));
+    assert_eq!(a.metadata().unwrap().tags, None);
+}
+
+#[test]
+fn counter_mimetype_parameters() {
+    use crate::metadata::Counter;
+
+    let counter: Counter = "text/html=10;text/html;raw=true=5;image/svg+xml;charset=utf-8=2"
+        .parse()
+        .unwrap();
+    assert_eq!(counter.get("text/html"), 10);
+    assert_eq!(counter.get("text/html;raw=true"), 5);
+    assert_eq!(counter.get("image/svg+xml;charset=utf-8"), 2);
+    assert_eq!(counter.total(), 17);
+    assert_eq!(counter.to_string().parse::<Counter>().unwrap(), counter);
+    assert!("text/html=1;text/plain".parse::<Counter>().is_err());
+}
+
+#[test]
+fn item_direct_access() {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let path = write_test_archive("zim-rs-item_direct_access.zim");
+    let a = Archive::new(path.to_str().unwrap()).unwrap();
+    // The stylesheet is in the uncompressed cluster.
+    let item = a
+        .get_entry_bypath_str("style.css")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    let access = item
+        .direct_access()
+        .expect("Uncompressed item should be directly accessible");
+    assert_eq!(access.path, a.parts()[0].path().unwrap());
+    let compressed = a.get_mainentry().unwrap().get_item(true).unwrap();
+    assert!(compressed.direct_access().is_none());
+
+    let mut file = std::fs::File::open(&access.path).unwrap();
+    file.seek(SeekFrom::Start(access.offset)).unwrap();
+    let mut bytes = vec![0u8; item.get_size() as usize];
+    file.read_exact(&mut bytes).unwrap();
+    assert_eq!(bytes, item.get_data().unwrap().data());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn item_reader() {
+    use crate::item::ItemReader;
+    use std::io::{BufRead, Read, Seek, SeekFrom};
+
+    let a = open_test_archive("zim-rs-item_reader.zim");
+    let item = a.get_mainentry().unwrap().get_item(true).unwrap();
+    let data = item.get_data().unwrap();
+
+    let mut copied = Vec::new();
+    let mut reader = ItemReader::new(item, 4);
+    let copied_len = std::io::copy(&mut reader, &mut copied).unwrap();
+    assert_eq!(copied_len, reader.size());
+    assert_eq!(copied, data.data());
+
+    // The reader owns its item and can move to another thread.
+    let item = reader.into_item();
+    let size = item.get_size();
+    let mut reader = std::thread::spawn(move || item.reader()).join().unwrap();
+    reader.seek(SeekFrom::End(-10)).unwrap();
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, &data.data()[data.data().len() - 10..]);
+    assert!(reader.fill_buf().unwrap().is_empty());
+    assert!(reader.seek(SeekFrom::Current(-(size as i64) - 1)).is_err());
+}
+
+#[test]
+fn blob_slice() {
+    let a = open_test_archive("zim-rs-blob_slice.zim");
+    let item = a.get_mainentry().unwrap().get_item(true).unwrap();
+    let blob = item.get_data().unwrap();
+
+    let slice = blob.slice(2..12).expect("Slicing failed");
+    assert_eq!(slice.size(), 10);
+    assert_eq!(slice.data(), &blob.data()[2..12]);
+    let inner = slice.slice(2..).unwrap();
+    assert_eq!(inner.data(), &blob.data()[4..12]);
+    assert!(slice.slice(..11).is_err());
+
+    #[cfg(feature = "bytes")]
+    {
+        let bytes = slice.clone().into_bytes();
+        assert_eq!(bytes.as_ptr(), slice.data().as_ptr());
+        assert_eq!(&bytes[..], slice.data());
+    }
+}
+
+#[test]
+fn uuid() {
+    use crate::uuid::Uuid;
+    use std::collections::HashSet;
+
+    let text = "0c7d2f6a-91b3-4e2f-8a6d-5b4e3c2d1f00";
+    let uuid: Uuid = text.parse().expect("Uuid should parse");
+    assert_eq!(uuid.to_string(), text);
+    assert_eq!(format!("{:?}", uuid), format!("Uuid({})", text));
+    assert_eq!(uuid.as_bytes()[0], 0x0c);
+    assert_eq!(Uuid::from_bytes(uuid.as_bytes()), uuid);
+    assert_eq!(
+        "0C7D2F6A-91B3-4E2F-8A6D-5B4E3C2D1F00"
+            .parse::<Uuid>()
+            .unwrap(),
+        uuid
+    );
+    assert!("0c7d2f6a91b34e2f8a6d5b4e3c2d1f00".parse::<Uuid>().is_err());
+    assert!("+c7d2f6a-91b3-4e2f-8a6d-5b4e3c2d1f00"
+        .parse::<Uuid>()
+        .is_err());
+    assert!(Uuid::from_bytes([0; 16]) < uuid);
+
+    let a = open_test_archive("zim-rs-uuid.zim");
+    let id = a.get_uuid().expect("Archive should have a uuid");
+    assert_eq!(id.as_bytes(), [7; 16]);
+    let as_string: String = (&id).try_into().unwrap();
+    assert_eq!(as_string.parse::<Uuid>().unwrap(), id);
+    let set: HashSet<Uuid> = [id, id, uuid].into_iter().collect();
+    assert_eq!(set.len(), 2);
+
+    #[cfg(feature = "uuid")]
+    {
+        let other: ::uuid::Uuid = uuid.into();
+        assert_eq!(other.to_string(), text);
+        assert_eq!(Uuid::from(other), uuid);
+    }
+}
+
+#[test]
+fn resolve_redirects() {
+    let a = open_test_archive("zim-rs-resolve_redirects.zim");
+    let redirect = a
+        .iter_efficient()
+        .unwrap()
+        .into_iter()
+        .map(|e| e.expect("Entry error!"))
+        .find(|e| e.is_redirect())
+        .expect("Archive should have a redirect");
+
+    let chain = redirect.resolve(10).expect("Redirect should resolve");
+    assert!(!chain.target.is_redirect());
+    assert_eq!(chain.hops[0].get_path(), redirect.get_path());
+    assert_eq!(
+        chain.target.get_path(),
+        redirect.get_item(true).unwrap().get_path()
+    );
+    assert!(matches!(
+        redirect.resolve(0),
+        Err(Error::TooManyRedirects(_))
+    ));
+
+    let main = a.get_mainentry().unwrap();
+    let chain = main.resolve(10).unwrap();
+    assert!(chain.hops.len() <= 10);
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+#[ignore = "needs the wiktionary archive at WIKT and its full-text index"]
+fn search_result_details() {
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let mut sr = Searcher::new(&a).expect("Searcher failed to create");
+    let q = Query::new("name").expect("Query failed to create");
+    let s = sr.search(&q).expect("Search failed to create");
+
+    let uuid = a.get_uuid().unwrap();
+    let mut previous_score = i32::MAX;
+    for result in s.get_results(0, 10).expect("Search matches failed") {
+        let result = result.expect("Search result error!");
+        assert_eq!(result.file_index(), 0);
+        assert_eq!(result.zim_id(), uuid);
+        assert!(result.archive().ptr_eq(&a));
+        assert!(result.score() <= previous_score);
+        previous_score = result.score();
+        assert!(!result.snippet().is_empty());
+    }
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+#[ignore = "needs the wiktionary archive at WIKT and its full-text index"]
+fn search_iter_all() {
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let mut sr = Searcher::new(&a).expect("Searcher failed to create");
+    let q = Query::new("name").expect("Query failed to create");
+    let s = sr.search(&q).expect("Search failed to create");
+
+    let paged: Vec<String> = s
+        .iter_all(7)
+        .take(25)
+        .map(|r| r.expect("Search result error!").entry().get_path())
+        .collect();
+    let direct: Vec<String> = s
+        .get_results(0, 25)
+        .expect("Search matches failed")
+        .into_iter()
+        .map(|r| r.expect("Search result error!").entry().get_path())
+        .collect();
+    assert_eq!(paged, direct);
+
+    let q = Query::new("zyzzyva").expect("Query failed to create");
+    let s = sr.search(&q).expect("Search failed to create");
+    let all = s.iter_all(3).count();
+    assert_eq!(all, s.get_results(0, 10_000).unwrap().into_iter().len());
+
+    assert!(matches!(
+        s.get_results(usize::MAX, 10),
+        Err(Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+#[ignore = "needs the wiktionary archive at WIKT and its full-text index"]
+fn search_cursor() {
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let mut sr = Searcher::new(&a).expect("Searcher failed to create");
+    let mut q = Query::new("name").expect("Query failed to create");
+    q.set_georange(48.85, 2.35, 1000.0);
+    let s = sr.search(&q).expect("Search failed to create");
+
+    let cursor = s.cursor(10).unwrap();
+    let encoded = cursor.to_string();
+    assert!(encoded
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+    let decoded: crate::cursor::SearchCursor = encoded.parse().expect("Cursor should decode");
+    assert_eq!(decoded, cursor);
+    assert_eq!(decoded.query(), "name");
+    assert_eq!(decoded.georange(), Some((48.85, 2.35, 1000.0)));
+    assert_eq!(decoded.archives(), [a.get_uuid().unwrap()]);
+    assert_eq!(decoded.offset(), 10);
+    assert!(encoded[1..].parse::<crate::cursor::SearchCursor>().is_err());
+    // Lone version 1 and 2 bytes.
+    for old in ["AQ", "Ag"] {
+        assert!(matches!(
+            old.parse::<crate::cursor::SearchCursor>(),
+            Err(Error::InvalidArgument(msg)) if msg.contains("version")
+        ));
+    }
+
+    let q = Query::new("name").expect("Query failed to create");
+    let s = sr.search(&q).expect("Search failed to create");
+    let paths = |set: SearchResultSet| {
+        set.into_iter()
+            .map(|r| r.expect("Search result error!").entry().get_path())
+            .collect::<Vec<_>>()
+    };
+    let cursor = s.cursor(5).unwrap();
+    let mut resumed_searcher = Searcher::new(&a).expect("Searcher failed to create");
+    let resumed = resumed_searcher
+        .resume(&cursor)
+        .expect("Search failed to resume");
+    assert_eq!(
+        paths(resumed.get_results(cursor.offset(), 5).unwrap()),
+        paths(s.get_results(5, 5).unwrap())
+    );
+
+    let mut ss = SuggestionSearcher::new(&a).expect("SuggestionSearcher failed to create");
+    let sug = ss.suggest("hello").expect("Suggestion failed");
+    let cursor: crate::cursor::SearchCursor = sug.cursor(3).unwrap().to_string().parse().unwrap();
+    let titles = |search: &SuggestionSearch| {
+        search
+            .get_results(3, 5)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.unwrap().get_title())
+            .collect::<Vec<_>>()
+    };
+    let resumed = ss.resume(&cursor).expect("Suggestion failed to resume");
+    assert_eq!(titles(&resumed), titles(&sug));
+
+    let other = crate::cursor::SearchCursor::new("hello", None, vec![Default::default()], 0);
+    assert!(matches!(ss.resume(&other), Err(Error::InvalidArgument(_))));
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+#[ignore = "needs the wiktionary archive at WIKT and its full-text index"]
+fn multi_searcher() {
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let mut ms = MultiSearcher::new();
+    let uuid = ms.add_archive(&a).expect("Archive should have an index");
+    assert!(matches!(ms.add_archive(&a), Err(Error::InvalidArgument(_))));
+    assert!(ms.archive(&uuid).unwrap().ptr_eq(&a));
+
+    let q = Query::new("name").expect("Query failed to create");
+    let s = ms.search(&q).expect("Search failed to create");
+    let mut previous_score = i32::MAX;
+    for result in s.get_results(0, 10).expect("Search matches failed") {
+        let result = result.expect("Search result error!");
+        assert_eq!(result.zim_id(), uuid);
+        assert!(ms
+            .archive(&result.zim_id())
+            .unwrap()
+            .ptr_eq(result.archive()));
+        assert!(result.score() <= previous_score);
+        previous_score = result.score();
+    }
+
+    let subset = ms.search_in(&q, &[uuid]).expect("Search failed to create");
+    assert_eq!(
+        subset.get_estimated_matches().unwrap(),
+        s.get_estimated_matches().unwrap()
+    );
+    assert!(matches!(
+        ms.search_in(&q, &[Default::default()]),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        ms.search_in(&q, &[]),
+        Err(Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+#[ignore = "needs the wiktionary archive at WIKT and its full-text index"]
+fn suggestion_entries() {
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let mut sr = SuggestionSearcher::new(&a).expect("SuggestionSearcher failed to create");
+    let s = sr
+        .suggest("color")
+        .expect("SuggestionSearch failed to create");
+
+    for suggestion in s.get_results(0, 10).unwrap() {
+        let suggestion = suggestion.unwrap();
+        let entry = suggestion.entry().expect("Suggested entry should exist");
+        assert_eq!(entry.get_path(), suggestion.get_path());
+        assert!(suggestion.item().unwrap().get_size() > 0);
+        assert_eq!(suggestion.archive().get_uuid(), a.get_uuid());
+    }
+
+    let folded = s.get_folded_results(0, 50).unwrap();
+    assert!(!folded.is_empty() && folded.len() <= 50);
+    let mut paths = std::collections::HashSet::new();
+    for suggestion in &folded {
+        assert!(!suggestion.entry().is_redirect());
+        assert!(paths.insert(suggestion.get_path()));
+    }
+
+    let path = std::env::temp_dir().join("zim-rs-suggestion_entries.zim");
+    let mut creator = Creator::new(&path, &CreatorConfig::new()).unwrap();
+    let front = Hints::new().front_article(true);
+    for (path, title) in [("color", "Color"), ("colorful", "Colorful")] {
+        let item = StringItem::new(path, "text/html", title, "<p>hue</p>");
+        creator.add_item(item.with_hints(front)).unwrap();
+    }
+    creator
+        .add_redirect("colour", "Colour", "color", front)
+        .unwrap();
+    creator.finish().unwrap();
+
+    let a = Archive::new(path.to_str().unwrap()).unwrap();
+    let mut sr = SuggestionSearcher::new(&a).unwrap();
+    let s = sr.suggest("colo").unwrap();
+    let folded = s.get_folded_results(0, 10).unwrap();
+    let paths = folded.iter().map(|f| f.get_path()).collect::<Vec<_>>();
+    assert_eq!(paths.len(), 2);
+    assert!(paths.contains(&"color".to_string()));
+    let color = folded.iter().find(|f| f.get_path() == "color").unwrap();
+    assert_eq!(color.aliases(), ["Colour"]);
+    assert!(color.resolve_error().is_none());
+    // Later pages never repeat an article folded on an earlier one.
+    let second = s.get_folded_results(1, 10).unwrap();
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].get_path(), paths[1]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+#[ignore = "needs the wiktionary archive at WIKT and its full-text index"]
+fn query_builder() {
+    let invalid = |builder: QueryBuilder| matches!(builder.build(), Err(Error::InvalidArgument(_)));
+    assert!(invalid(QueryBuilder::new("name").georange(
+        91.0,
+        0.0,
+        1.0,
+        DistanceUnit::Meters
+    )));
+    assert!(invalid(QueryBuilder::new("name").georange(
+        0.0,
+        -181.0,
+        1.0,
+        DistanceUnit::Meters
+    )));
+    assert!(invalid(QueryBuilder::new("name").georange(
+        0.0,
+        0.0,
+        f32::NAN,
+        DistanceUnit::Meters
+    )));
+    assert!(invalid(QueryBuilder::new("name").georange(
+        0.0,
+        0.0,
+        0.0,
+        DistanceUnit::Meters
+    )));
+    assert!(invalid(
+        QueryBuilder::new("name").path_prefix(&"a".repeat(70000))
+    ));
+
+    let q = Query::builder("name")
+        .georange(45.0, 7.0, 2.0, DistanceUnit::Kilometers)
+        .build()
+        .unwrap();
+    assert_eq!(q.georange(), Some((45.0, 7.0, 2000.0)));
+    assert_eq!(q.clone().georange(), q.georange());
+
+    let text: std::sync::Arc<str> = "name".into();
+    let q = QueryBuilder::new(text.clone())
+        .mimetype("text/html")
+        .path_prefix("a")
+        .build()
+        .unwrap();
+    assert!(std::sync::Arc::ptr_eq(q.text(), &text));
+
+    let a = Archive::new(WIKT).expect("File should exist!");
+    let mut sr = Searcher::new(&a).expect("Searcher failed to create");
+    let s = sr.search(&q.clone()).expect("Search failed to create");
+    for result in s.iter_all(20).take(40) {
+        let result = result.expect("Search result error!");
+        assert!(result.entry().get_path().starts_with('a'));
+        assert_eq!(
+            result
+                .entry()
+                .get_item(true)
+                .unwrap()
+                .get_mimetype()
+                .unwrap(),
+            "text/html"
+        );
+    }
+    let all = s
+        .iter_all(20)
+        .take(10)
+        .map(|r| r.expect("Search result error!").entry().get_path())
+        .collect::<Vec<_>>();
+    let paths = |set: SearchResultSet| {
+        set.into_iter()
+            .map(|r| r.expect("Search result error!").entry().get_path())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(paths(s.get_results(0, 10).unwrap()), all);
+    assert_eq!(paths(s.get_results(5, 5).unwrap()), all[5..]);
+    assert_eq!(paths(s.get_results(2, 3).unwrap()), all[2..5]);
+    let fifth = s.get_results(0, 10).unwrap().into_iter().nth(4);
+    assert_eq!(
+        fifth.map(|r| r.unwrap().entry().get_path()).as_ref(),
+        all.get(4)
+    );
+    let mut results = s.get_results(0, 10).unwrap().into_iter();
+    assert_eq!(results.len(), all.len());
+    let last = results.next_back().map(|r| r.unwrap().entry().get_path());
+    assert_eq!(last.as_ref(), all.last());
+    assert!(s.get_estimated_matches().unwrap() >= all.len());
+
+    let cursor = s.cursor(0).unwrap();
+    let resumed = sr.resume(&cursor).expect("Search failed to resume");
+    assert_eq!(resumed.cursor(0).unwrap(), cursor);
+
+    let q = QueryBuilder::new("name").max_results(7).build().unwrap();
+    assert_eq!(q.max_results(), Some(7));
+    let s = sr.search(&q).expect("Search failed to create");
+    assert_eq!(s.get_results(0, 10).unwrap().into_iter().len(), 7);
+    assert_eq!(s.get_results(5, 10).unwrap().into_iter().len(), 2);
+    assert_eq!(s.get_results(7, 10).unwrap().into_iter().len(), 0);
+    assert_eq!(s.iter_all(3).count(), 7);
+    assert!(s.get_estimated_matches().unwrap() <= 7);
+    let cursor: crate::cursor::SearchCursor = s.cursor(3).unwrap().to_string().parse().unwrap();
+    assert_eq!(cursor.max_results(), Some(7));
+    let resumed = sr.resume(&cursor).expect("Search failed to resume");
+    assert_eq!(resumed.get_results(3, 10).unwrap().into_iter().len(), 4);
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+fn write_archive() {
+    let path = std::env::temp_dir().join("zim-rs-write_archive.zim");
+    let config = CreatorConfig::new()
+        .compression(Compression::Zstd)
+        .cluster_size(64 * 1024)
+        .indexing("eng")
+        .nb_workers(2);
+    let mut creator = Creator::new(&path, &config).expect("Creator failed to start");
+    creator.add_metadata("Title", "Round trip").unwrap();
+    creator.add_metadata("Language", "eng").unwrap();
+    let png = b"\x89PNG\r\n\x1a\nnot really an image";
+    creator.add_illustration(48, png).unwrap();
+    creator
+        .add_item(
+            StringItem::new(
+                "home",
+                "text/html",
+                "Home",
+                "<html><body><p>Welcome to the aardvark home</p></body></html>",
+            )
+            .with_hints(Hints::new().front_article(true)),
+        )
+        .unwrap();
+    creator
+        .add_redirect("start", "Start", "home", Hints::new())
+        .unwrap();
+    creator.add_alias("index", "Index", "home").unwrap();
+    creator.set_main_path("home");
+    creator.finish().expect("Creator failed to finish");
+
+    let a = Archive::new(path.to_str().unwrap()).expect("Written archive should open");
+    assert!(a.check());
+    assert_eq!(a.get_metadata("Title").unwrap(), "Round trip");
+    assert_eq!(a.metadata().unwrap().language, ["eng"]);
+    assert_eq!(
+        a.get_mainentry()
+            .unwrap()
+            .get_item(true)
+            .unwrap()
+            .get_path(),
+        "home"
+    );
+
+    let home = a
+        .get_entry_bypath_str("home")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    assert_eq!(home.get_mimetype().unwrap(), "text/html");
+    let start = a.get_entry_bypath_str("start").unwrap();
+    assert!(start.is_redirect());
+    assert_eq!(start.get_redirect_entry().unwrap().get_path(), "home");
+    let index = a.get_entry_bypath_str("index").unwrap();
+    assert!(!index.is_redirect());
+    assert_eq!(
+        index.get_item(false).unwrap().get_data().unwrap().data(),
+        home.get_data().unwrap().data()
+    );
+    assert_eq!(
+        a.get_illustration_item(48)
+            .unwrap()
+            .get_data()
+            .unwrap()
+            .data(),
+        png
+    );
+
+    assert!(a.has_fulltext_index());
+    let mut sr = Searcher::new(&a).expect("Searcher failed to create");
+    let s = sr.search(&Query::new("aardvark").unwrap()).unwrap();
+    assert!(s.get_estimated_matches().unwrap() >= 1);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(not(feature = "pure-rust"))]
+struct BigItem {
+    size: u64,
+}
+
+#[cfg(not(feature = "pure-rust"))]
+impl WriterItem for BigItem {
+    fn path(&self) -> String {
+        "big.bin".to_string()
+    }
+
+    fn title(&self) -> String {
+        "Big".to_string()
+    }
+
+    fn mimetype(&self) -> String {
+        "application/octet-stream".to_string()
+    }
+
+    fn hints(&self) -> Hints {
+        Hints::new().compress(false)
+    }
+
+    fn content_provider(&self) -> Result<Box<dyn ContentProvider>, Error> {
+        Ok(Box::new(
+            ReadContentProvider::new(PatternReader { pos: 0 }, self.size).with_chunk_size(4096),
+        ))
+    }
+}
+
+/// Endless `i % 251` bytes, generated as they are read.
+#[cfg(not(feature = "pure-rust"))]
+struct PatternReader {
+    pos: u64,
+}
+
+#[cfg(not(feature = "pure-rust"))]
+impl std::io::Read for PatternReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        for byte in buf.iter_mut() {
+            *byte = (self.pos % 251) as u8;
+            self.pos += 1;
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Panics when libzim asks for its content.
+#[cfg(not(feature = "pure-rust"))]
+struct PanicItem;
+
+#[cfg(not(feature = "pure-rust"))]
+impl WriterItem for PanicItem {
+    fn path(&self) -> String {
+        "panic".to_string()
+    }
+
+    fn title(&self) -> String {
+        "Panic".to_string()
+    }
+
+    fn mimetype(&self) -> String {
+        "text/plain".to_string()
+    }
+
+    fn content_provider(&self) -> Result<Box<dyn ContentProvider>, Error> {
+        panic!("no content");
+    }
+}
+
+/// Claims more content than its provider has.
+#[cfg(not(feature = "pure-rust"))]
+struct ShortItem;
+
+#[cfg(not(feature = "pure-rust"))]
+impl WriterItem for ShortItem {
+    fn path(&self) -> String {
+        "short".to_string()
+    }
+
+    fn title(&self) -> String {
+        "Short".to_string()
+    }
+
+    fn mimetype(&self) -> String {
+        "text/plain".to_string()
+    }
+
+    fn content_provider(&self) -> Result<Box<dyn ContentProvider>, Error> {
+        Ok(Box::new(ReadContentProvider::new(&b"abc"[..], 5)))
+    }
+}
+
+/// An HTML page read from a new one-shot reader each time libzim asks for it.
+#[cfg(not(feature = "pure-rust"))]
+struct ReopenedItem {
+    html: &'static str,
+    opened: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(not(feature = "pure-rust"))]
+impl WriterItem for ReopenedItem {
+    fn path(&self) -> String {
+        "reopened".to_string()
+    }
+
+    fn title(&self) -> String {
+        "Reopened".to_string()
+    }
+
+    fn mimetype(&self) -> String {
+        "text/html".to_string()
+    }
+
+    fn content_provider(&self) -> Result<Box<dyn ContentProvider>, Error> {
+        self.opened
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let reader = self.html.as_bytes();
+        Ok(Box::new(ReadContentProvider::new(
+            reader,
+            self.html.len() as u64,
+        )))
+    }
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+fn write_streamed_items() {
+    let path = std::env::temp_dir().join("zim-rs-write_streamed_items.zim");
+    let mut creator = Creator::new(&path, &CreatorConfig::new()).unwrap();
+    creator.add_item(BigItem { size: 100_003 }).unwrap();
+    creator.finish().expect("Creator failed to finish");
+
+    let a = Archive::new(path.to_str().unwrap()).unwrap();
+    let item = a
+        .get_entry_bypath_str("big.bin")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    assert_eq!(item.get_size(), 100_003);
+    let data = item.get_data().unwrap();
+    assert!(data
+        .data()
+        .iter()
+        .enumerate()
+        .all(|(i, &b)| b == (i % 251) as u8));
+    std::fs::remove_file(&path).unwrap();
+
+    let path = std::env::temp_dir().join("zim-rs-write_reopened_item.zim");
+    let opened = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let html = "<html><body><p>The pangolin rolls into a ball</p></body></html>";
+    let mut creator = Creator::new(&path, &CreatorConfig::new().indexing("eng")).unwrap();
+    creator
+        .add_item(ReopenedItem {
+            html,
+            opened: opened.clone(),
+        })
+        .unwrap();
+    creator.finish().expect("Creator failed to finish");
+    // Once to index the text, once to store the content.
+    assert!(opened.load(std::sync::atomic::Ordering::SeqCst) >= 2);
+    let a = Archive::new(path.to_str().unwrap()).unwrap();
+    let item = a
+        .get_entry_bypath_str("reopened")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    assert_eq!(item.get_data().unwrap().data(), html.as_bytes());
+    let mut sr = Searcher::new(&a).expect("Searcher failed to create");
+    let s = sr.search(&Query::new("pangolin").unwrap()).unwrap();
+    let results = s.get_results(0, 10).unwrap();
+    let paths = results
+        .into_iter()
+        .map(|r| r.unwrap().entry().get_path())
+        .collect::<Vec<_>>();
+    assert_eq!(paths, ["reopened"]);
+    std::fs::remove_file(&path).unwrap();
+
+    let mut short = ReadContentProvider::new(&b"abc"[..], 5);
+    assert_eq!(short.feed().unwrap(), b"abc");
+    assert!(matches!(short.feed(), Err(Error::Io(_))));
+
+    let path = std::env::temp_dir().join("zim-rs-write_short_item.zim");
+    let mut creator = Creator::new(&path, &CreatorConfig::new()).unwrap();
+    let result = creator.add_item(ShortItem).and_then(|_| creator.finish());
+    assert!(matches!(result, Err(Error::Io(_))));
+    let _ = std::fs::remove_file(&path);
+
+    let path = std::env::temp_dir().join("zim-rs-write_panic_item.zim");
+    let result = std::panic::catch_unwind(|| {
+        let mut creator = Creator::new(&path, &CreatorConfig::new()).unwrap();
+        creator.add_item(PanicItem).and_then(|_| creator.finish())
+    });
+    let payload = result.expect_err("the panic should be resumed");
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"no content"));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+#[cfg(not(feature = "pure-rust"))]
+fn write_index_data() {
+    let path = std::env::temp_dir().join("zim-rs-write_index_data.zim");
+    let config = CreatorConfig::new().indexing("eng");
+    let mut creator = Creator::new(&path, &config).unwrap();
+    let page = |name: &str| {
+        format!(
+            "<html><body><nav>sidebar navigation</nav><p>{}</p></body></html>",
+            name
+        )
+    };
+    let front = Hints::new().front_article(true);
+    creator
+        .add_item(
+            StringItem::new("paris", "text/html", "Paris", page("Paris"))
+                .with_hints(front)
+                .with_index_data(
+                    TextIndexData::new("Paris", "capital city on the Seine")
+                        .keywords("lutetia")
+                        .geo_position(48.8566, 2.3522),
+                ),
+        )
+        .unwrap();
+    creator
+        .add_item(
+            StringItem::new("lyon", "text/html", "Lyon", page("Lyon"))
+                .with_hints(front)
+                .with_index_data(
+                    TextIndexData::new("Lyon", "city on the Rhone").geo_position(45.764, 4.8357),
+                ),
+        )
+        .unwrap();
+    creator.finish().unwrap();
+
+    let a = Archive::new(path.to_str().unwrap()).unwrap();
+    let mut sr = Searcher::new(&a).unwrap();
+    let paths = |sr: &mut Searcher, q: &Query| {
+        sr.search(q)
+            .unwrap()
+            .get_results(0, 10)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.unwrap().entry().get_path())
+            .collect::<Vec<_>>()
+    };
+
+    assert!(paths(&mut sr, &Query::new("navigation").unwrap()).is_empty());
+    assert_eq!(paths(&mut sr, &Query::new("lutetia").unwrap()), ["paris"]);
+    let s = sr.search(&Query::new("seine").unwrap()).unwrap();
+    let result = s
+        .get_results(0, 1)
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.word_count(), Some(5));
+
+    let mut q = Query::new("city").unwrap();
+    q.set_georange(48.85, 2.35, 10_000.0);
+    assert_eq!(paths(&mut sr, &q), ["paris"]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// A cluster of `blobs`, with 8 byte offsets if `extended`, compressed with
+/// `compression` (1 none, 4 xz, 5 zstd).
+fn zim_cluster(compression: u8, extended: bool, blobs: &[&[u8]]) -> Vec<u8> {
+    let offset_size = if extended { 8 } else { 4 };
+    let mut data = Vec::new();
+    let mut offset = offset_size * (blobs.len() as u64 + 1);
+    for len in blobs.iter().map(|blob| blob.len() as u64).chain([0]) {
+        match extended {
+            true => data.extend(offset.to_le_bytes()),
+            false => data.extend((offset as u32).to_le_bytes()),
+        }
+        offset += len;
+    }
+    blobs.iter().for_each(|blob| data.extend_from_slice(blob));
+    let data = match compression {
+        4 => {
+            let mut xz = Vec::new();
+            lzma_rs::xz_compress(&mut &data[..], &mut xz).unwrap();
+            xz
+        }
+        5 => ruzstd::encoding::compress_to_vec(
+            &data[..],
+            ruzstd::encoding::CompressionLevel::Fastest,
+        ),
+        _ => data,
+    };
+    let info = compression | if extended { 0x10 } else { 0 };
+    [vec![info], data].concat()
+}
+
+/// A dirent, `target` being `[cluster, blob]` or `[redirect index]`.
+fn zim_dirent(mimetype: u16, namespace: u8, target: &[u32], path: &str, title: &str) -> Vec<u8> {
+    let mut dirent = mimetype.to_le_bytes().to_vec();
+    dirent.extend([0, namespace, 0, 0, 0, 0]);
+    target.iter().for_each(|t| dirent.extend(t.to_le_bytes()));
+    dirent.extend([path.as_bytes(), b"\0", title.as_bytes(), b"\0"].concat());
+    dirent
+}
+
+/// An archive of version `major.minor` made of `dirents`, sorted by path, and
+/// `clusters`, with `titles` as the header title list and an md5 checksum.
+fn zim_archive(
+    (major, minor): (u16, u16),
+    mimetypes: &[u8],
+    dirents: &[Vec<u8>],
+    titles: &[u32],
+    clusters: &[Vec<u8>],
+    main_page: u32,
+) -> Vec<u8> {
+    use md5::{Digest, Md5};
+
+    let path_ptr_pos = 80 + mimetypes.len() as u64;
+    let title_ptr_pos = path_ptr_pos + 8 * dirents.len() as u64;
+    let cluster_ptr_pos = title_ptr_pos + 4 * titles.len() as u64;
+    let mut pos = cluster_ptr_pos + 8 * clusters.len() as u64;
+    let mut ptrs = |chunks: &[Vec<u8>]| {
+        chunks
+            .iter()
+            .flat_map(|chunk| {
+                let ptr = pos;
+                pos += chunk.len() as u64;
+                ptr.to_le_bytes()
+            })
+            .collect::<Vec<u8>>()
+    };
+    let dirent_ptrs = ptrs(dirents);
+    let cluster_ptrs = ptrs(clusters);
+
+    let mut zim = 0x044D_495Au32.to_le_bytes().to_vec();
+    zim.extend(major.to_le_bytes());
+    zim.extend(minor.to_le_bytes());
+    zim.extend([7u8; 16]);
+    zim.extend((dirents.len() as u32).to_le_bytes());
+    zim.extend((clusters.len() as u32).to_le_bytes());
+    for value in [path_ptr_pos, title_ptr_pos, cluster_ptr_pos, 80] {
+        zim.extend(value.to_le_bytes());
+    }
+    zim.extend(main_page.to_le_bytes());
+    zim.extend(u32::MAX.to_le_bytes());
+    zim.extend(pos.to_le_bytes());
+    zim.extend(mimetypes);
+    zim.extend(dirent_ptrs);
+    titles
+        .iter()
+        .for_each(|index| zim.extend(index.to_le_bytes()));
+    zim.extend(cluster_ptrs);
+    zim.extend(dirents.concat());
+    zim.extend(clusters.concat());
+    let checksum = Md5::digest(&zim);
+    zim.extend(checksum);
+    zim
+}
+
+/// A small archive with the new namespace scheme and one cluster per compression
+/// the native reader supports: none, zstd and xz.
+fn test_archive() -> Vec<u8> {
+    let dirents = [
+        zim_dirent(1, b'C', &[1, 0], "main", "Main page"),
+        zim_dirent(0xffff, b'C', &[0], "other", ""),
+        zim_dirent(0, b'C', &[0, 0], "style.css", ""),
+        zim_dirent(2, b'C', &[2, 0], "z.txt", ""),
+        zim_dirent(2, b'M', &[0, 1], "Title", ""),
+        zim_dirent(0xffff, b'W', &[0], "mainPage", ""),
+    ];
+    let clusters = [
+        zim_cluster(1, false, &[b"body {}", b"Test archive"]),
+        zim_cluster(5, false, &[b"<h1>Hello</h1>"]),
+        zim_cluster(4, false, &[b"last"]),
+    ];
+    zim_archive(
+        (6, 1),
+        b"text/css\0text/html\0text/plain\0\0",
+        &dirents,
+        &[0, 1, 2, 3, 4, 5],
+        &clusters,
+        0,
+    )
+}
+
+/// [`test_archive`] written to a temporary file named `name`, for the caller to remove.
+fn write_test_archive(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, test_archive()).unwrap();
+    path
+}
+
+/// [`test_archive`] opened from a temporary file named `name`.
+fn open_test_archive(name: &str) -> Archive {
+    let path = write_test_archive(name);
+    let archive = Archive::new(path.to_str().unwrap()).expect("Test archive should open");
+    std::fs::remove_file(&path).unwrap();
+    archive
+}
+
+/// Open `zim` as the native reader would a file named `name`.
+#[cfg(feature = "pure-rust")]
+fn open_native(name: &str, zim: &[u8]) -> Result<Archive, Error> {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, zim).unwrap();
+    let archive = Archive::new(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    archive
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn native_reader() {
+    let path = std::env::temp_dir().join("zim-rs-native_reader.zim");
+    std::fs::write(&path, test_archive()).unwrap();
+    let a = Archive::open_with_config(&path, &OpenConfig::new().preload_dirents(3)).unwrap();
+    assert_eq!(a.get_dirent_lookup_cache_max_size(), 3);
+    let b = Archive::new(path.to_str().unwrap()).unwrap();
+    assert_eq!(b.get_dirent_lookup_cache_max_size(), 1024);
+
+    assert!(a.has_new_namespace_scheme());
+    assert_eq!(a.get_all_entrycount(), 6);
+    assert_eq!(a.get_entrycount(), 4);
+    assert_eq!(a.get_articlecount(), 4);
+    assert_eq!(a.get_uuid().unwrap().as_bytes(), [7u8; 16]);
+    assert_eq!(a.get_metadata_keys().unwrap(), ["Title"]);
+    assert_eq!(a.get_metadata("Title").unwrap(), "Test archive");
+    assert!(!a.has_fulltext_index());
+    assert!(a.has_checksum() && a.check());
+
+    let main = a.get_mainentry().unwrap().get_item(true).unwrap();
+    assert_eq!(main.get_path(), "main");
+    assert_eq!(main.get_title(), "Main page");
+    assert_eq!(main.get_mimetype().unwrap(), "text/html");
+    assert_eq!(main.get_data().unwrap().data(), b"<h1>Hello</h1>");
+    assert!(main.direct_access().is_none());
+
+    let other = a.get_entry_bypath_str("other").unwrap();
+    assert!(other.is_redirect());
+    assert!(other.get_item(false).is_err());
+    assert_eq!(other.resolve(4).unwrap().target.get_path(), "main");
+
+    let css = a
+        .get_entry_bypath_str("style.css")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    assert_eq!(css.get_data().unwrap().data(), b"body {}");
+    let access = css.direct_access().expect("uncompressed item");
+    let bytes = std::fs::read(&access.path).unwrap();
+    assert_eq!(&bytes[access.offset as usize..][..7], b"body {}");
+
+    let last = a
+        .get_entry_bytitle_str("z.txt")
+        .unwrap()
+        .get_item(false)
+        .unwrap();
+    assert_eq!(last.get_size(), 4);
+    assert_eq!(last.get_data_offset(1, 2).unwrap().data(), b"as");
+    assert_eq!(last.get_data_offset(2, 10).unwrap().data(), b"st");
+    assert!(matches!(
+        a.get_entry_bypath_str("missing"),
+        Err(Error::EntryNotFound(_))
+    ));
+
+    let paths = |range: EntryRangePath| {
+        range
+            .into_iter()
+            .map(|e| e.unwrap().get_path())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        paths(a.iter_by_path().unwrap()),
+        ["main", "other", "style.css", "z.txt"]
+    );
+    assert_eq!(paths(a.find_by_path("s").unwrap()), ["style.css"]);
+    let titles = a
+        .iter_by_title()
+        .unwrap()
+        .into_iter()
+        .rev()
+        .map(|e| e.unwrap().get_title())
+        .collect::<Vec<_>>();
+    assert_eq!(titles, ["z.txt", "style.css", "other", "Main page"]);
+    assert_eq!(a.find_by_title("o").unwrap().len(), 1);
+    assert_eq!(a.iter_efficient().unwrap().into_iter().count(), 4);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn native_extended_clusters() {
+    let dirents = [
+        zim_dirent(0, b'C', &[0, 0], "a", ""),
+        zim_dirent(0, b'C', &[0, 1], "b", ""),
+        zim_dirent(0, b'C', &[1, 0], "c", ""),
+        zim_dirent(0, b'C', &[2, 0], "d", ""),
+    ];
+    let clusters = [
+        zim_cluster(1, true, &[b"plain", b""]),
+        zim_cluster(5, true, &[b"zstd"]),
+        zim_cluster(4, true, &[b"xz"]),
+    ];
+    let zim = zim_archive(
+        (6, 1),
+        b"text/plain\0\0",
+        &dirents,
+        &[0, 1, 2, 3],
+        &clusters,
+        u32::MAX,
+    );
+    let a = open_native("zim-rs-native_extended_clusters.zim", &zim).unwrap();
+    let data = |path: &str| {
+        let item = a
+            .get_entry_bypath_str(path)
+            .unwrap()
+            .get_item(false)
+            .unwrap();
+        item.get_data().unwrap().data().to_vec()
+    };
+    assert_eq!(data("a"), b"plain");
+    assert_eq!(data("b"), b"");
+    assert_eq!(data("c"), b"zstd");
+    assert_eq!(data("d"), b"xz");
+    assert!(a.check());
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn native_padded_clusters() {
+    // Compressed clusters end with their stream, whatever follows before the next one.
+    let padding = vec![0xAAu8; 100_000];
+    let dirents = [
+        zim_dirent(0, b'C', &[0, 0], "a", ""),
+        zim_dirent(0, b'C', &[1, 0], "b", ""),
+    ];
+    let clusters = [
+        [zim_cluster(5, false, &[b"zstd"]), padding.clone()].concat(),
+        [zim_cluster(4, false, &[b"xz"]), padding].concat(),
+    ];
+    let zim = zim_archive(
+        (6, 1),
+        b"text/plain\0\0",
+        &dirents,
+        &[0, 1],
+        &clusters,
+        u32::MAX,
+    );
+    let a = open_native("zim-rs-native_padded_clusters.zim", &zim).unwrap();
+    for (path, content) in [("a", &b"zstd"[..]), ("b", b"xz")] {
+        let item = a
+            .get_entry_bypath_str(path)
+            .unwrap()
+            .get_item(false)
+            .unwrap();
+        assert_eq!(item.get_data().unwrap().data(), content);
+    }
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn native_old_namespaces() {
+    let dirents = [
+        zim_dirent(0, b'-', &[0, 0], "favicon", ""),
+        zim_dirent(1, b'A', &[0, 1], "Foo", "Foo"),
+        zim_dirent(0xffff, b'A', &[1], "Foobar", "Foobar"),
+        zim_dirent(1, b'A', &[0, 2], "Zoo", "Zoo"),
+        zim_dirent(0, b'I', &[0, 0], "logo.png", ""),
+        zim_dirent(2, b'M', &[0, 3], "Title", ""),
+    ];
+    let clusters = [zim_cluster(
+        1,
+        false,
+        &[b"png", b"<p>Foo</p>", b"<p>Zoo</p>", b"Old archive"],
+    )];
+    let zim = zim_archive(
+        (5, 0),
+        b"image/png\0text/html\0text/plain\0\0",
+        &dirents,
+        &[0, 1, 2, 3, 4, 5],
+        &clusters,
+        1,
+    );
+    let a = open_native("zim-rs-native_old_namespaces.zim", &zim).unwrap();
+
+    assert!(!a.has_new_namespace_scheme());
+    assert_eq!(a.get_entrycount(), 6);
+    assert_eq!(a.get_articlecount(), 3);
+    assert_eq!(a.get_metadata("Title").unwrap(), "Old archive");
+    assert_eq!(a.get_illustration_sizes().unwrap(), [48]);
+    assert_eq!(a.get_mainentry().unwrap().get_path(), "A/Foo");
+
+    let foobar = a.get_entry_bypath_str("A/Foobar").unwrap();
+    assert_eq!(foobar.get_redirect_entry().unwrap().get_path(), "A/Foo");
+    assert_eq!(a.get_entry_bypath_str("Zoo").unwrap().get_path(), "A/Zoo");
+    assert_eq!(
+        a.get_entry_bypath_str("logo.png").unwrap().get_path(),
+        "I/logo.png"
+    );
+    assert!(a.get_entry_bypath_str("M/Missing").is_err());
+    assert_eq!(a.get_entry_bytitle_str("Zoo").unwrap().get_path(), "A/Zoo");
+
+    let paths = |range: EntryRangePath| {
+        range
+            .into_iter()
+            .map(|e| e.unwrap().get_path())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        paths(a.find_by_path("A/Foo").unwrap()),
+        ["A/Foo", "A/Foobar"]
+    );
+    assert_eq!(paths(a.find_by_path("A").unwrap()).len(), 3);
+    assert_eq!(paths(a.find_by_path("Foo").unwrap()).len(), 0);
+    assert_eq!(paths(a.iter_by_path().unwrap()).len(), 6);
+    assert_eq!(a.find_by_title("Foo").unwrap().len(), 2);
+    assert_eq!(a.iter_by_title().unwrap().len(), 6);
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn native_title_listings() {
+    let listing = |indexes: &[u32]| {
+        indexes
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect::<Vec<u8>>()
+    };
+    let v0 = listing(&[1, 2, 0, 3, 4, 5]);
+    let v1 = listing(&[1, 0]);
+    let dirents = [
+        zim_dirent(0, b'C', &[0, 0], "a", "Zebra"),
+        zim_dirent(0, b'C', &[0, 1], "b", "Apple"),
+        zim_dirent(0, b'C', &[0, 2], "c", "Mango"),
+        zim_dirent(1, b'M', &[0, 3], "Title", ""),
+        zim_dirent(2, b'X', &[1, 0], "listing/titleOrdered/v0", ""),
+        zim_dirent(2, b'X', &[1, 1], "listing/titleOrdered/v1", ""),
+    ];
+    let clusters = [
+        zim_cluster(5, false, &[b"zebra", b"apple", b"mango", b"Listings"]),
+        zim_cluster(1, false, &[&v0, &v1]),
+    ];
+    // The header title list is left in path order, only the listings are sorted.
+    let zim = zim_archive(
+        (6, 1),
+        b"text/html\0text/plain\0application/octet-stream+zimlisting\0\0",
+        &dirents,
+        &[0, 1, 2, 3, 4, 5],
+        &clusters,
+        u32::MAX,
+    );
+    let a = open_native("zim-rs-native_title_listings.zim", &zim).unwrap();
+
+    assert_eq!(a.get_entrycount(), 3);
+    assert_eq!(a.get_articlecount(), 2);
+    let titles = a
+        .iter_by_title()
+        .unwrap()
+        .into_iter()
+        .map(|e| e.unwrap().get_title())
+        .collect::<Vec<_>>();
+    assert_eq!(titles, ["Apple", "Mango", "Zebra"]);
+    assert_eq!(a.get_entry_bytitle_str("Mango").unwrap().get_path(), "c");
+    assert_eq!(a.get_entry_bytitle_index(2).unwrap().get_path(), "a");
+    assert!(a.get_entry_bytitle_str("Mang").is_err());
+    assert_eq!(a.find_by_title("Ma").unwrap().len(), 1);
+    for _ in 0..8 {
+        let path = a.get_randomentry().unwrap().get_path();
+        assert!(
+            path == "a" || path == "b",
+            "{} is not a front article",
+            path
+        );
+    }
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn native_split_parts() {
+    let zim = test_archive();
+    // Split in the middle of the uncompressed "body {}" blob, and once more near the end.
+    let blob = zim.windows(7).position(|w| w == b"body {}").unwrap();
+    let cuts = [0, blob + 3, zim.len() - 20, zim.len()];
+    let path = std::env::temp_dir().join("zim-rs-native_split_parts.zim");
+    let parts = ["aa", "ab", "ac"].map(|suffix| {
+        let mut part = path.clone().into_os_string();
+        part.push(suffix);
+        std::path::PathBuf::from(part)
+    });
+    for (part, range) in parts.iter().zip(cuts.windows(2)) {
+        std::fs::write(part, &zim[range[0]..range[1]]).unwrap();
+    }
+
+    let a = Archive::new(path.to_str().unwrap()).expect("Split archive should open");
+    assert!(a.is_multipart());
+    assert_eq!(a.parts().len(), 3);
+    assert_eq!(a.get_filesize(), zim.len() as u64);
+    assert_eq!(a.get_metadata("Title").unwrap(), "Test archive");
+    assert!(a.check());
+    let item = |path: &str| {
+        a.get_entry_bypath_str(path)
+            .unwrap()
+            .get_item(false)
+            .unwrap()
+    };
+    let css = item("style.css");
+    assert_eq!(css.get_data().unwrap().data(), b"body {}");
+    assert!(css.direct_access().is_none());
+    assert_eq!(item("z.txt").get_data().unwrap().data(), b"last");
+    for part in &parts {
+        std::fs::remove_file(part).unwrap();
+    }
+}
+
+#[test]
+#[cfg(feature = "pure-rust")]
+fn native_malformed() {
+    let open = |zim: &[u8]| open_native("zim-rs-native_malformed.zim", zim);
+    let zim = test_archive();
+    let patched = |pos: usize, bytes: &[u8]| {
+        let mut zim = zim.clone();
+        zim[pos..pos + bytes.len()].copy_from_slice(bytes);
+        zim
+    };
+
+    assert!(matches!(open(b"ZIM"), Err(Error::InvalidFormat(_))));
+    assert!(matches!(open(&[0; 80]), Err(Error::InvalidFormat(_))));
+    assert!(matches!(
+        open(&patched(4, &7u16.to_le_bytes())),
+        Err(Error::ZimFileFormat(_))
+    ));
+    assert!(matches!(
+        open(&patched(32, &u64::MAX.to_le_bytes())),
+        Err(Error::ZimFileFormat(_))
+    ));
+    assert!(matches!(
+        open(&zim[..zim.len() / 2]),
+        Err(Error::ZimFileFormat(_))
+    ));
+
+    // A flipped byte of content only shows in the checksum.
+    let blob = zim.windows(4).position(|w| w == b"body").unwrap();
+    let a = open(&patched(blob, b"BODY")).unwrap();
+    assert!(!a.check());
+
+    let broken = |cluster: Vec<u8>| {
+        let dirents = [
+            zim_dirent(0, b'C', &[0, 0], "a", ""),
+            zim_dirent(0, b'C', &[1, 0], "b", ""),
+        ];
+        let zim = zim_archive(
+            (6, 1),
+            b"text/plain\0\0",
+            &dirents,
+            &[0, 1],
+            &[cluster],
+            u32::MAX,
+        );
+        let a = open(&zim).unwrap();
+        let data = a
+            .get_entry_bypath_str("a")
+            .unwrap()
+            .get_item(false)
+            .and_then(|item| item.get_data());
+        assert!(
+            matches!(data, Err(Error::ZimFileFormat(_))),
+            "{:?}",
+            data.err()
+        );
+        assert!(a
+            .get_entry_bypath_str("b")
+            .unwrap()
+            .get_item(false)
+            .and_then(|item| item.get_data())
+            .is_err());
+    };
+    broken(vec![9, 8, 0, 0, 0, 8, 0, 0, 0]);
+    broken(vec![1, 12, 0, 0, 0, 14, 0, 0, 0, 13, 0, 0, 0, 0, 0]);
+    broken(vec![1, 8, 0, 0, 0, 99, 0, 0, 0]);
+    broken(vec![5, 1, 2, 3, 4]);
+    broken(vec![4]);
+}
@@ -1,52 +1,136 @@
+use std::{fmt, str::FromStr};
+
+use crate::error::Error;
+#[cfg(not(feature = "pure-rust"))]
+use crate::{binding::ffi, cxx::string_from_ptr};
+#[cfg(not(feature = "pure-rust"))]
 use cxx::UniquePtr;
-use zim_sys::binding::ffi;
 
+/// The 16 bytes uuid identifying an archive.
+///
+/// Formatted and parsed in the canonical hyphenated form
+/// (`xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`), as libzim does.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Uuid {
-    ptr: UniquePtr<ffi::Uuid>,
+    bytes: [u8; 16],
 }
 
+#[cfg(not(feature = "pure-rust"))]
 impl Uuid {
-    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Uuid>) -> Result<Uuid, ()> {
-        match ptr.is_null() {
-            true => Err(()),
-            false => Ok(Uuid { ptr }),
-        }
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Uuid>) -> Result<Uuid, Error> {
+        string_from_ptr(ffi::uuid_std_string(ptr.as_ref().unwrap())).parse()
     }
 
-    fn inner_ref(&self) -> &ffi::Uuid {
-        self.ptr.as_ref().unwrap()
+    pub fn new_generate(value: &str) -> Result<Uuid, Error> {
+        Uuid::from_ptr(ffi::uuid_generate(value))
     }
+}
 
-    pub fn new_empty() -> Result<Uuid, ()> {
-        Uuid::from_ptr(ffi::uuid_ctor())
+/// The md5 digest of `value`, or of the current time if it is empty, as libzim's
+/// `zim::Uuid::generate`.
+#[cfg(feature = "pure-rust")]
+impl Uuid {
+    pub fn new_generate(value: &str) -> Result<Uuid, Error> {
+        use md5::{Digest, Md5};
+
+        let mut md5 = Md5::new();
+        match value.is_empty() {
+            true => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default();
+                md5.update(now.as_nanos().to_le_bytes());
+                md5.update(std::process::id().to_le_bytes());
+            }
+            false => md5.update(value.as_bytes()),
+        }
+        Ok(Uuid::from_bytes(md5.finalize().into()))
+    }
+}
+
+/// Same behaviour as libzim's `zim::Uuid` constructors.
+impl Uuid {
+    pub fn new_empty() -> Result<Uuid, Error> {
+        Ok(Uuid::default())
     }
 
     /// This data should really only be 16 bytes long, but if it's longer it won't hurt, it will fail if shorter however
-    pub fn new_from_bytes(uuid: &str) -> Result<Uuid, ()> {
-        Uuid::from_ptr(ffi::uuid_ctor_str(uuid))
+    pub fn new_from_bytes(uuid: &str) -> Result<Uuid, Error> {
+        match uuid.as_bytes().get(..16) {
+            Some(bytes) => Ok(Uuid::from_bytes(bytes.try_into().unwrap())),
+            None => Err(Error::InvalidArgument(format!(
+                "{:?} is not a valid uuid",
+                uuid
+            ))),
+        }
+    }
+}
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Uuid {
+        Uuid { bytes }
     }
 
-    pub fn new_generate(value: &str) -> Result<Uuid, ()> {
-        Uuid::from_ptr(ffi::uuid_generate(value))
+    pub fn as_bytes(&self) -> [u8; 16] {
+        self.bytes
     }
 }
 
-impl PartialEq for Uuid {
-    fn eq(&self, other: &Self) -> bool {
-        ffi::uuid_operator_eq(self.inner_ref(), other.inner_ref())
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.bytes.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Uuid({})", self)
     }
 }
 
-impl Eq for Uuid {}
+impl FromStr for Uuid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidArgument(format!("{:?} is not a hyphenated uuid", s));
+        let groups = s.split('-').map(str::len).collect::<Vec<_>>();
+        let digits = s.bytes().filter(|&b| b != b'-').collect::<Vec<u8>>();
+        if groups != [8, 4, 4, 4, 12] || !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(invalid());
+        }
+        let hex = |digit: u8| (digit as char).to_digit(16).unwrap() as u8;
+        let mut bytes = [0u8; 16];
+        for (byte, pair) in bytes.iter_mut().zip(digits.chunks(2)) {
+            *byte = hex(pair[0]) << 4 | hex(pair[1]);
+        }
+        Ok(Uuid { bytes })
+    }
+}
 
 impl TryInto<String> for &Uuid {
-    type Error = ();
+    type Error = Error;
 
     fn try_into(self) -> Result<String, Self::Error> {
-        let res = ffi::uuid_std_string(self.inner_ref());
-        match res.is_null() {
-            true => Err(()),
-            false => Ok(res.as_ref().unwrap().to_string()),
-        }
+        Ok(self.to_string())
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<::uuid::Uuid> for Uuid {
+    fn from(uuid: ::uuid::Uuid) -> Self {
+        Uuid::from_bytes(uuid.into_bytes())
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<Uuid> for ::uuid::Uuid {
+    fn from(uuid: Uuid) -> Self {
+        ::uuid::Uuid::from_bytes(uuid.as_bytes())
     }
 }
@@ -0,0 +1,676 @@
+use std::{
+    any::Any,
+    io::{Cursor, Read},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use cxx::UniquePtr;
+
+use crate::{
+    archive::path_bytes,
+    binding::ffi::{self, GeoPosition},
+    error::Error,
+    item::DEFAULT_CHUNK_SIZE,
+};
+
+/// Mimetype libzim gives to metadata added with [`Creator::add_metadata`].
+const TEXT_METADATA_MIMETYPE: &str = "text/plain;charset=utf-8";
+
+/// Keys of libzim's `zim::writer::HintKeys`.
+const HINT_COMPRESS: u8 = 0;
+const HINT_FRONT_ARTICLE: u8 = 1;
+
+/// Writes a new archive.
+///
+/// Everything added is only readable once [`Creator::finish`] returns; dropping a
+/// creator before that leaves an incomplete file behind.
+///
+/// ```no_run
+/// # use zim_rs::writer::{Creator, CreatorConfig, StringItem};
+/// let config = CreatorConfig::new().indexing("eng").nb_workers(4);
+/// let mut creator = Creator::new("out.zim", &config).unwrap();
+/// creator.add_metadata("Title", "Example").unwrap();
+/// creator
+///     .add_item(StringItem::new("home", "text/html", "Home", "<p>Hello</p>"))
+///     .unwrap();
+/// creator.set_main_path("home");
+/// creator.finish().unwrap();
+/// ```
+pub struct Creator {
+    ptr: UniquePtr<ffi::Creator>,
+    fault: Arc<Fault>,
+}
+
+/// Options of a [`Creator`], which libzim only accepts before the archive is started.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CreatorConfig {
+    verbose: bool,
+    compression: Option<Compression>,
+    cluster_size: Option<u64>,
+    indexing: Option<String>,
+    nb_workers: Option<u32>,
+}
+
+/// Compression of the clusters holding compressible content.
+///
+/// Values are those of libzim's `zim::Compression`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Compression {
+    None = 1,
+    #[default]
+    Zstd = 5,
+}
+
+/// How libzim should store an item. Hints left unset are guessed by libzim.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Hints {
+    front_article: Option<bool>,
+    compress: Option<bool>,
+}
+
+/// An entry to write, whose content libzim pulls from its [`ContentProvider`]
+/// when it is ready to store it.
+///
+/// libzim keeps items until their cluster is written and calls them from its
+/// worker threads, hence the `Send + Sync` bound.
+pub trait WriterItem: Send + Sync {
+    fn path(&self) -> String;
+
+    fn title(&self) -> String;
+
+    fn mimetype(&self) -> String;
+
+    fn hints(&self) -> Hints {
+        Hints::default()
+    }
+
+    /// Called when libzim reads the content: when it is about to be written, and when
+    /// indexing a `text/html` item without [`WriterItem::index_data`], to extract its
+    /// text.
+    ///
+    /// Every call must return a new provider feeding the content from its start, so
+    /// an item streaming from a reader has to open it again on each call.
+    fn content_provider(&self) -> Result<Box<dyn ContentProvider>, Error>;
+
+    /// What goes into the full-text index for this item. By default libzim
+    /// extracts it from the content of `text/html` items.
+    fn index_data(&self) -> Option<Box<dyn IndexData>> {
+        None
+    }
+}
+
+/// The full-text index data of a [`WriterItem`], used instead of what libzim would
+/// extract from its content.
+pub trait IndexData: Send + Sync {
+    /// Whether the item is indexed at all.
+    fn has_index_data(&self) -> bool {
+        true
+    }
+
+    fn title(&self) -> String;
+
+    /// Plain text to index.
+    fn content(&self) -> String;
+
+    /// Space separated words indexed along the content, which do not appear in snippets.
+    fn keywords(&self) -> String {
+        String::new()
+    }
+
+    /// Returned by [`SearchResult::word_count`](crate::search::SearchResult::word_count).
+    fn word_count(&self) -> u32;
+
+    /// `(latitude, longitude)` in degrees, matched by
+    /// [`Query::set_georange`](crate::search::Query::set_georange).
+    fn geo_position(&self) -> Option<(f64, f64)> {
+        None
+    }
+}
+
+/// [`IndexData`] given as plain values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextIndexData {
+    title: String,
+    content: String,
+    keywords: String,
+    word_count: u32,
+    geo_position: Option<(f64, f64)>,
+}
+
+/// The content of a [`WriterItem`], handed to libzim one chunk at a time.
+pub trait ContentProvider: Send {
+    /// Total size of the content. The chunks fed must add up to exactly this size.
+    fn size(&self) -> u64;
+
+    /// The next chunk of content, empty once everything was fed.
+    fn feed(&mut self) -> Result<Vec<u8>, Error>;
+}
+
+/// A [`ContentProvider`] reading `size` bytes from any [`Read`], so that the
+/// content never has to be held in memory at once.
+pub struct ReadContentProvider<R> {
+    reader: R,
+    size: u64,
+    remaining: u64,
+    chunk_size: u64,
+}
+
+/// An item whose whole content is held in memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StringItem {
+    path: String,
+    mimetype: String,
+    title: String,
+    content: Arc<[u8]>,
+    hints: Hints,
+    index_data: Option<TextIndexData>,
+}
+
+/// A [`WriterItem`] handed to libzim, see `RustItem` in `binding.h`.
+pub(crate) struct WriterItemBox {
+    item: Box<dyn WriterItem>,
+    fault: Arc<Fault>,
+}
+
+/// A [`ContentProvider`] handed to libzim, see `RustContentProvider` in `binding.h`.
+pub(crate) struct ContentProviderBox {
+    provider: Box<dyn ContentProvider>,
+    fault: Arc<Fault>,
+}
+
+/// The [`IndexData`] of a [`WriterItem`] handed to libzim, `None` to let libzim
+/// extract it from the content. See `RustIndexData` in `binding.h`.
+pub(crate) struct IndexDataBox {
+    data: Option<Box<dyn IndexData>>,
+    fault: Arc<Fault>,
+}
+
+/// The first failure of the callbacks of a [`Creator`].
+///
+/// libzim only sees a message, possibly from one of its worker threads, and throws
+/// it again later from whichever creator call comes next. That call returns the
+/// original failure instead.
+#[derive(Default)]
+struct Fault(Mutex<Option<Failure>>);
+
+enum Failure {
+    Error(Error),
+    Panic(Box<dyn Any + Send>),
+}
+
+impl Creator {
+    pub(crate) fn from_ptr(ptr: UniquePtr<ffi::Creator>) -> Creator {
+        Creator {
+            ptr,
+            fault: Arc::default(),
+        }
+    }
+
+    /// Start writing an archive at `path`, replacing any existing file.
+    pub fn new<P: AsRef<Path>>(path: P, config: &CreatorConfig) -> Result<Creator, Error> {
+        let mut creator = Creator::from_ptr(ffi::creator_ctor());
+        config.apply(&mut creator);
+        let path = path_bytes(path.as_ref())?;
+        ffi::creator_startZimCreation(creator.ptr.pin_mut(), &path)?;
+        Ok(creator)
+    }
+
+    /// Add an entry with content. An error returned by the item or its content
+    /// provider makes this, or [`Creator::finish`] if libzim pulls the content
+    /// later, fail.
+    ///
+    /// A panic of the item or its content provider is caught before it reaches
+    /// libzim, and resumed from the same calls.
+    pub fn add_item<I: WriterItem + 'static>(&mut self, item: I) -> Result<(), Error> {
+        let item = Box::new(WriterItemBox {
+            item: Box::new(item),
+            fault: self.fault.clone(),
+        });
+        let result = ffi::creator_addItem(self.ptr.pin_mut(), item);
+        self.check(result)
+    }
+
+    /// Add an entry at `path` redirecting to the entry at `target_path`.
+    ///
+    /// Only the front article hint applies to a redirect: a front article redirect is
+    /// listed in title suggestions under its own title.
+    pub fn add_redirect(
+        &mut self,
+        path: &str,
+        title: &str,
+        target_path: &str,
+        hints: Hints,
+    ) -> Result<(), Error> {
+        Ok(ffi::creator_addRedirection(
+            self.ptr.pin_mut(),
+            path,
+            title,
+            target_path,
+            hints.get(HINT_FRONT_ARTICLE),
+        )?)
+    }
+
+    /// Add an entry at `path` sharing the content of the entry at `target_path`,
+    /// which must already have been added. Unlike a redirect, it is a regular entry.
+    pub fn add_alias(&mut self, path: &str, title: &str, target_path: &str) -> Result<(), Error> {
+        Ok(ffi::creator_addAlias(
+            self.ptr.pin_mut(),
+            path,
+            title,
+            target_path,
+        )?)
+    }
+
+    /// Set the metadata `name`, such as `Title`, `Language` or `Date`.
+    pub fn add_metadata(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        Ok(ffi::creator_addMetadata(
+            self.ptr.pin_mut(),
+            name,
+            value.as_bytes(),
+            TEXT_METADATA_MIMETYPE,
+        )?)
+    }
+
+    /// Add a square PNG illustration of `size` pixels, see
+    /// [`Archive::get_illustration_item`](crate::archive::Archive::get_illustration_item).
+    pub fn add_illustration(&mut self, size: u32, png: &[u8]) -> Result<(), Error> {
+        Ok(ffi::creator_addIllustration(self.ptr.pin_mut(), size, png)?)
+    }
+
+    /// Path of the entry returned by [`Archive::get_mainentry`](crate::archive::Archive::get_mainentry).
+    pub fn set_main_path(&mut self, path: &str) {
+        ffi::creator_setMainPath(self.ptr.pin_mut(), path);
+    }
+
+    /// Write the remaining clusters, the index and the pointer lists, and close the file.
+    pub fn finish(mut self) -> Result<(), Error> {
+        let result = ffi::creator_finishZimCreation(self.ptr.pin_mut());
+        self.check(result)
+    }
+
+    /// The failure of a callback behind the exception libzim threw, if any.
+    fn check(&self, result: Result<(), cxx::Exception>) -> Result<(), Error> {
+        let err = match result {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        match self.fault.take() {
+            Some(Failure::Error(err)) => Err(err),
+            Some(Failure::Panic(payload)) => panic::resume_unwind(payload),
+            None => Err(err.into()),
+        }
+    }
+}
+
+impl CreatorConfig {
+    pub fn new() -> CreatorConfig {
+        CreatorConfig::default()
+    }
+
+    /// Print progress while writing.
+    pub fn verbose(mut self, verbose: bool) -> CreatorConfig {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn compression(mut self, compression: Compression) -> CreatorConfig {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Size in bytes above which a cluster is closed and compressed.
+    pub fn cluster_size(mut self, size: u64) -> CreatorConfig {
+        self.cluster_size = Some(size);
+        self
+    }
+
+    /// Build a full-text and a title index, stemming for this ISO 639-3 language.
+    pub fn indexing(mut self, language: &str) -> CreatorConfig {
+        self.indexing = Some(language.to_string());
+        self
+    }
+
+    /// Number of threads compressing clusters and indexing content.
+    pub fn nb_workers(mut self, nb_workers: u32) -> CreatorConfig {
+        self.nb_workers = Some(nb_workers);
+        self
+    }
+
+    fn apply(&self, creator: &mut Creator) {
+        let mut ptr = creator.ptr.pin_mut();
+        ffi::creator_configVerbose(ptr.as_mut(), self.verbose);
+        if let Some(compression) = self.compression {
+            ffi::creator_configCompression(ptr.as_mut(), compression as u8);
+        }
+        if let Some(size) = self.cluster_size {
+            ffi::creator_configClusterSize(ptr.as_mut(), size);
+        }
+        if let Some(language) = &self.indexing {
+            ffi::creator_configIndexing(ptr.as_mut(), true, language);
+        }
+        if let Some(nb_workers) = self.nb_workers {
+            ffi::creator_configNbWorkers(ptr.as_mut(), nb_workers);
+        }
+    }
+}
+
+impl Hints {
+    pub fn new() -> Hints {
+        Hints::default()
+    }
+
+    /// `FRONT_ARTICLE`: whether the item is an article, counted as such and listed in
+    /// title suggestions, rather than a resource such as an image or a stylesheet.
+    pub fn front_article(mut self, front_article: bool) -> Hints {
+        self.front_article = Some(front_article);
+        self
+    }
+
+    /// `COMPRESS`: whether the content goes in a compressed cluster.
+    pub fn compress(mut self, compress: bool) -> Hints {
+        self.compress = Some(compress);
+        self
+    }
+
+    pub fn get_front_article(&self) -> Option<bool> {
+        self.front_article
+    }
+
+    pub fn get_compress(&self) -> Option<bool> {
+        self.compress
+    }
+
+    fn get(&self, key: u8) -> i64 {
+        let value = match key {
+            HINT_COMPRESS => self.compress,
+            HINT_FRONT_ARTICLE => self.front_article,
+            _ => None,
+        };
+        value.map_or(-1, i64::from)
+    }
+}
+
+impl<R: Read + Send> ReadContentProvider<R> {
+    pub fn new(reader: R, size: u64) -> ReadContentProvider<R> {
+        ReadContentProvider {
+            reader,
+            size,
+            remaining: size,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Read and feed at most `chunk_size` bytes at a time, [`DEFAULT_CHUNK_SIZE`] by default.
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> ReadContentProvider<R> {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+}
+
+impl<R: Read + Send> ContentProvider for ReadContentProvider<R> {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn feed(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.remaining.min(self.chunk_size);
+        let mut chunk = Vec::with_capacity(len as usize);
+        (&mut self.reader).take(len).read_to_end(&mut chunk)?;
+        if (chunk.len() as u64) < len {
+            return Err(Error::Io(format!(
+                "content ended {} bytes short of its size of {}",
+                self.remaining - chunk.len() as u64,
+                self.size
+            )));
+        }
+        self.remaining -= len;
+        Ok(chunk)
+    }
+}
+
+impl TextIndexData {
+    /// Index `content` under `title`, counting its words.
+    pub fn new(title: &str, content: &str) -> TextIndexData {
+        TextIndexData {
+            title: title.to_string(),
+            content: content.to_string(),
+            keywords: String::new(),
+            word_count: content.split_whitespace().count() as u32,
+            geo_position: None,
+        }
+    }
+
+    pub fn keywords(mut self, keywords: &str) -> TextIndexData {
+        self.keywords = keywords.to_string();
+        self
+    }
+
+    pub fn word_count(mut self, word_count: u32) -> TextIndexData {
+        self.word_count = word_count;
+        self
+    }
+
+    pub fn geo_position(mut self, latitude: f64, longitude: f64) -> TextIndexData {
+        self.geo_position = Some((latitude, longitude));
+        self
+    }
+}
+
+impl IndexData for TextIndexData {
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn content(&self) -> String {
+        self.content.clone()
+    }
+
+    fn keywords(&self) -> String {
+        self.keywords.clone()
+    }
+
+    fn word_count(&self) -> u32 {
+        self.word_count
+    }
+
+    fn geo_position(&self) -> Option<(f64, f64)> {
+        self.geo_position
+    }
+}
+
+impl StringItem {
+    pub fn new(path: &str, mimetype: &str, title: &str, content: impl Into<Vec<u8>>) -> StringItem {
+        StringItem {
+            path: path.to_string(),
+            mimetype: mimetype.to_string(),
+            title: title.to_string(),
+            content: content.into().into(),
+            hints: Hints::default(),
+            index_data: None,
+        }
+    }
+
+    pub fn with_hints(mut self, hints: Hints) -> StringItem {
+        self.hints = hints;
+        self
+    }
+
+    /// Index `index_data` rather than what libzim extracts from the content.
+    pub fn with_index_data(mut self, index_data: TextIndexData) -> StringItem {
+        self.index_data = Some(index_data);
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn mimetype(&self) -> &str {
+        &self.mimetype
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+}
+
+impl WriterItem for StringItem {
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn mimetype(&self) -> String {
+        self.mimetype.clone()
+    }
+
+    fn hints(&self) -> Hints {
+        self.hints
+    }
+
+    fn content_provider(&self) -> Result<Box<dyn ContentProvider>, Error> {
+        let size = self.content.len() as u64;
+        Ok(Box::new(ReadContentProvider::new(
+            Cursor::new(self.content.clone()),
+            size,
+        )))
+    }
+
+    fn index_data(&self) -> Option<Box<dyn IndexData>> {
+        let data = self.index_data.clone()?;
+        Some(Box::new(data))
+    }
+}
+
+impl WriterItemBox {
+    fn call<T>(&self, f: impl FnOnce(&dyn WriterItem) -> Result<T, Error>) -> Result<T, String> {
+        self.fault.guard(|| f(&*self.item))
+    }
+}
+
+impl IndexDataBox {
+    fn call<T>(&self, f: impl FnOnce(&dyn IndexData) -> T) -> Result<T, String> {
+        match &self.data {
+            Some(data) => self.fault.guard(|| Ok(f(&**data))),
+            None => Err("no custom index data".to_string()),
+        }
+    }
+}
+
+impl Fault {
+    /// Run a callback, recording its error or panic for [`Creator::check`] and only
+    /// telling libzim that it failed.
+    fn guard<T>(&self, f: impl FnOnce() -> Result<T, Error>) -> Result<T, String> {
+        let failure = match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(Ok(value)) => return Ok(value),
+            Ok(Err(err)) => Failure::Error(err),
+            Err(payload) => Failure::Panic(payload),
+        };
+        let msg = match &failure {
+            Failure::Error(err) => err.to_string(),
+            Failure::Panic(_) => "writer callback panicked".to_string(),
+        };
+        // Nothing panics while holding the lock, so a poisoned one is still consistent.
+        let mut first = self.0.lock().unwrap_or_else(|poison| poison.into_inner());
+        first.get_or_insert(failure);
+        Err(msg)
+    }
+
+    fn take(&self) -> Option<Failure> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poison| poison.into_inner())
+            .take()
+    }
+}
+
+pub(crate) fn writer_item_path(item: &WriterItemBox) -> Result<String, String> {
+    item.call(|item| Ok(item.path()))
+}
+
+pub(crate) fn writer_item_title(item: &WriterItemBox) -> Result<String, String> {
+    item.call(|item| Ok(item.title()))
+}
+
+pub(crate) fn writer_item_mimetype(item: &WriterItemBox) -> Result<String, String> {
+    item.call(|item| Ok(item.mimetype()))
+}
+
+pub(crate) fn writer_item_hint(item: &WriterItemBox, key: u8) -> Result<i64, String> {
+    item.call(|item| Ok(item.hints().get(key)))
+}
+
+pub(crate) fn writer_item_content_provider(
+    item: &WriterItemBox,
+) -> Result<Box<ContentProviderBox>, String> {
+    let provider = item.call(|item| item.content_provider())?;
+    Ok(Box::new(ContentProviderBox {
+        provider,
+        fault: item.fault.clone(),
+    }))
+}
+
+pub(crate) fn writer_item_index_data(item: &WriterItemBox) -> Result<Box<IndexDataBox>, String> {
+    let data = item.call(|item| Ok(item.index_data()))?;
+    Ok(Box::new(IndexDataBox {
+        data,
+        fault: item.fault.clone(),
+    }))
+}
+
+pub(crate) fn content_provider_size(provider: &ContentProviderBox) -> Result<u64, String> {
+    provider.fault.guard(|| Ok(provider.provider.size()))
+}
+
+pub(crate) fn content_provider_feed(provider: &mut ContentProviderBox) -> Result<Vec<u8>, String> {
+    provider.fault.guard(|| provider.provider.feed())
+}
+
+pub(crate) fn index_data_is_default(data: &IndexDataBox) -> bool {
+    data.data.is_none()
+}
+
+pub(crate) fn index_data_has_index_data(data: &IndexDataBox) -> Result<bool, String> {
+    data.call(|data| data.has_index_data())
+}
+
+pub(crate) fn index_data_title(data: &IndexDataBox) -> Result<String, String> {
+    data.call(|data| data.title())
+}
+
+pub(crate) fn index_data_content(data: &IndexDataBox) -> Result<String, String> {
+    data.call(|data| data.content())
+}
+
+pub(crate) fn index_data_keywords(data: &IndexDataBox) -> Result<String, String> {
+    data.call(|data| data.keywords())
+}
+
+pub(crate) fn index_data_word_count(data: &IndexDataBox) -> Result<u32, String> {
+    data.call(|data| data.word_count())
+}
+
+pub(crate) fn index_data_geo_position(data: &IndexDataBox) -> Result<GeoPosition, String> {
+    data.call(|data| match data.geo_position() {
+        Some((latitude, longitude)) => GeoPosition {
+            valid: true,
+            latitude,
+            longitude,
+        },
+        None => GeoPosition {
+            valid: false,
+            latitude: 0.0,
+            longitude: 0.0,
+        },
+    })
+}